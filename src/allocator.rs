@@ -0,0 +1,76 @@
+/* The allocator 'AtomicCell' and 'AtomicVec' take their ACNodes from.
+
+With the 'allocator_api' cargo feature (nightly only) these are std's 'Allocator' and 'Global', so any allocator written against
+std works. Without it, a stand-in with the same shape is used: the two methods the cells need, and 'Global' forwarding to the
+global allocator. An allocator written against the stand-in keeps working once the feature is on, as long as it implements
+the std trait too. */
+
+#[cfg(feature = "allocator_api")]
+pub use alloc::alloc::{AllocError, Allocator, Global};
+
+#[cfg(not(feature = "allocator_api"))]
+pub use self::stand_in::{AllocError, Allocator, Global};
+
+#[cfg(not(feature = "allocator_api"))]
+mod stand_in {
+    use alloc::alloc::Layout;
+    use core::ptr::NonNull;
+
+    /* The allocation failed. */
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct AllocError;
+
+    impl core::fmt::Display for AllocError {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            f.write_str("memory allocation failed")
+        }
+    }
+
+    impl core::error::Error for AllocError {}
+
+    /// The subset of `std::alloc::Allocator` the cells use.
+    ///
+    /// # Safety
+    /// Same contract as `std::alloc::Allocator`: memory returned by `allocate` stays valid until it is passed to `deallocate`
+    /// of this allocator (or a clone of it), and `deallocate` is only called with memory `allocate` returned, with the same layout.
+    pub unsafe trait Allocator {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError>;
+
+        /// # Safety
+        /// `ptr` was returned by `allocate` of this allocator with this `layout`, and isn't used anymore.
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout);
+    }
+
+    unsafe impl<A: Allocator + ?Sized> Allocator for &A {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+            (**self).allocate(layout)
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            (**self).deallocate(ptr, layout)
+        }
+    }
+
+    /* The global allocator, i.e. whatever '#[global_allocator]' says. */
+    #[derive(Clone, Copy, Debug, Default)]
+    pub struct Global;
+
+    unsafe impl Allocator for Global {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+            // ACNodes are never zero-sized, but the trait allows it.
+            if layout.size() == 0 {
+                let dangling = NonNull::new(layout.align() as *mut u8).ok_or(AllocError)?;
+                return Ok(NonNull::slice_from_raw_parts(dangling, 0));
+            }
+
+            let memory = NonNull::new(unsafe { alloc::alloc::alloc(layout) }).ok_or(AllocError)?;
+            Ok(NonNull::slice_from_raw_parts(memory, layout.size()))
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            if layout.size() != 0 {
+                alloc::alloc::dealloc(ptr.as_ptr(), layout);
+            }
+        }
+    }
+}
@@ -1,4 +1,6 @@
 // WIP Ignore
+// DefaultHasher is std only: once the map is back, it goes behind the 'std' feature (or takes a BuildHasher).
+// Its lookups should return Result<_, crate::error::KeyNotFound> instead of Option<()> when it is.
 
 /* use crate::primitives::AtomicCell::*;
 use crate::collections::MlcVec::*;
@@ -1,12 +1,15 @@
-#[deny(clippy::pedantic)]
 use crate::primitives::AtomicCell::*;
-use std::{sync::Arc, fmt::Debug};
+use crate::primitives::Reclaim::CounterReclaim;
+use crate::allocator::{Allocator, Global};
+use crate::error::{IndexOutOfBounds, UpdateResult};
+use alloc::{sync::Arc, vec::Vec};
+use core::fmt::Debug;
 
 // WIP Ignore
 
 // TODO: Add Iterator support
-pub struct AtomicVec<T: Debug> {
-    pub(crate) beam: AtomicCell<Vec<Arc<T>>>,
+pub struct AtomicVec<T: Debug, A: Allocator = Global> {
+    pub(crate) beam: AtomicCell<Vec<Arc<T>>, CounterReclaim, A>,
 }
 
 impl<T: Debug> AtomicVec<T> {
@@ -21,12 +24,33 @@ impl<T: Debug> AtomicVec<T> {
             beam: AtomicCell::new(Vec::with_capacity(cap))
          }
     }
+}
+
+impl<T: Debug> Default for AtomicVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 'alloc' is what the beam's ACNodes are allocated with, see 'AtomicCell::new_in'. The Vec's buffer stays on the global heap.
+impl<T: Debug, A: Allocator> AtomicVec<T, A> {
+    pub fn new_in(alloc: A) -> Self {
+        Self {
+            beam: AtomicCell::new_in(Vec::new(), alloc),
+        }
+    }
+
+    pub fn new_with_capacity_in(cap: usize, alloc: A) -> Self {
+        Self { 
+            beam: AtomicCell::new_in(Vec::with_capacity(cap), alloc)
+         }
+    }
 
     pub fn get_beam(&self) -> Arc<Vec<Arc<T>>> {
         self.beam.load()
     }
 
-    pub fn update<O, F>(&self, func: F) -> std::thread::Result<O> 
+    pub fn update<O, F>(&self, func: F) -> UpdateResult<O> 
     where
     F: FnMut(Arc<Vec<Arc<T>>>) -> (Arc<Vec<Arc<T>>>, O)
     {
@@ -36,30 +60,33 @@ impl<T: Debug> AtomicVec<T> {
     // MlcVec does not expose a write handle to individual T's. Use Wrappers such as AtomicCell or Mutex to modify through shared references.
     // This enables using only one wrapper for better matrices: MlcVec<MlcVec<Wrapper<T>>>
     pub fn get(&self, idx: usize) -> Option<Arc<T>> {
-        match self.beam.load().get(idx) {
-            Some(z) => Some(z.clone()),
-            None => None,
-        }
+        self.beam.load().get(idx).cloned()
     }
 
     pub fn push(&self, data: T) {
         let new = Arc::new(data);
-        // Assume clone does not panic
-        let _ = self.beam.fetch_update::<(), _>(|vec| {
-            let mut next_vec = (*vec).clone();
-            next_vec.push(new.clone());
-            (Arc::new(next_vec), ())
-    });
+        // 'beam' keeps the default panic policy, so it is never poisoned.
+        self.beam.rcu(|vec| vec.push(new.clone())).expect("never poisoned");
     }
 
-    // TODO: Add pop for any index
     pub fn pop(&self) -> Option<Arc<T>> {
-        self.beam.fetch_update::<Option<Arc<T>>, _>(|vec| {
-            let mut next_vec = (*vec).clone();
-            let output = next_vec.pop();
-            (Arc::new(next_vec), output)
+        self.beam.rcu(Vec::pop).expect("never poisoned")
+    }
+
+    // Removes the element at 'idx', shifting the ones after it down. Nothing is stored if 'idx' is out of bounds.
+    pub fn remove(&self, idx: usize) -> Result<Arc<T>, IndexOutOfBounds> {
+        let removed = self.beam.try_update(|vec| {
+            (idx < vec.len()).then(|| {
+                let mut next_vec = vec.clone();
+                next_vec.remove(idx);
+                next_vec
+            })
         })
-        // Assume clone does not panic
-        .unwrap()
+        .expect("never poisoned");
+
+        match removed {
+            Ok(previous) => Ok(previous[idx].clone()),
+            Err(current) => Err(IndexOutOfBounds { index: idx, len: current.len() }),
+        }
     }
 }
@@ -1,2 +1,5 @@
+// Module files are named after the type they hold.
+#![allow(non_snake_case)]
+
 pub mod MlcMap;
-pub mod MlcVec;
\ No newline at end of file
+pub mod MlcVec;
@@ -0,0 +1,168 @@
+/* The errors of everything in the crate that can fail. All of them implement 'Error', so they go through '?' into a Box<dyn Error>
+or any error type of yours that converts from them. */
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use core::any::Any;
+use core::error::Error;
+use core::fmt::{self, Debug, Display, Formatter};
+
+/* What the updates that run a closure return: those of 'AtomicCell' and the 'fetch_update' of the copy cells. With std a panic in the
+closure is caught and handed back as Err, see 'PanicPolicy'. Without std there is no catching, a panic goes straight through (and usually
+aborts). Either way the cell is left as it was, nothing stays protected. */
+pub type UpdateResult<O> = Result<O, UpdateError>;
+
+/* Why an update stored nothing. 'fetch_update' itself never gives up, only 'try_fetch_update' and 'fetch_update_until' return
+'RetriesExhausted'. 'Poisoned' is returned by every update of a poisoned 'AtomicCell' without calling its closure, see 'PanicPolicy::Poison'. */
+#[derive(Debug)]
+pub enum UpdateError {
+    Panicked(ClosurePanicked),
+    RetriesExhausted(RetriesExhausted),
+    Poisoned,
+}
+
+impl Display for UpdateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            UpdateError::Panicked(panicked) => Display::fmt(panicked, f),
+            UpdateError::RetriesExhausted(exhausted) => Display::fmt(exhausted, f),
+            UpdateError::Poisoned => write!(f, "an update closure of this cell panicked, it refuses updates until 'clear_poison'"),
+        }
+    }
+}
+
+impl Error for UpdateError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            UpdateError::Panicked(panicked) => Some(panicked),
+            UpdateError::RetriesExhausted(exhausted) => Some(exhausted),
+            UpdateError::Poisoned => None,
+        }
+    }
+}
+
+impl From<ClosurePanicked> for UpdateError {
+    fn from(panicked: ClosurePanicked) -> Self {
+        UpdateError::Panicked(panicked)
+    }
+}
+
+impl From<RetriesExhausted> for UpdateError {
+    fn from(exhausted: RetriesExhausted) -> Self {
+        UpdateError::RetriesExhausted(exhausted)
+    }
+}
+
+/* A compare-and-swap found something else than expected. 'rejected' is the value that was not stored, handed back so a retry doesn't
+have to clone or allocate again. 'current' is what the cell held instead: its value, or its version for 'compare_exchange_version'. */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CasFailed<R, C = R> {
+    pub current: C,
+    pub rejected: R,
+}
+
+impl<R, C> Display for CasFailed<R, C> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "the cell held something else than expected, nothing was stored")
+    }
+}
+
+impl<R: Debug, C: Debug> Error for CasFailed<R, C> {}
+
+/* An update closure panicked, nothing was stored. Send and Sync like any other error, so it goes through '?' into
+Box<dyn Error + Send + Sync> too. */
+pub struct ClosurePanicked {
+    /* The panic message, if it was one ('panic!' with a literal or a format string). */
+    message: Option<String>,
+    payload: Payload,
+}
+
+/* The payload itself is only Send. It is only ever reached by value ('into_payload'), never through a shared reference, so
+sharing a '&ClosurePanicked' between threads can't touch it. */
+struct Payload(Box<dyn Any + Send + 'static>);
+
+unsafe impl Sync for Payload {}
+
+impl ClosurePanicked {
+    /* Wraps what 'catch_unwind' returned. */
+    #[cfg(feature = "std")]
+    pub(crate) fn new(payload: Box<dyn Any + Send + 'static>) -> Self {
+        let message = match payload.downcast_ref::<&'static str>() {
+            Some(message) => Some(String::from(*message)),
+            None => payload.downcast_ref::<String>().cloned(),
+        };
+        Self {
+            message,
+            payload: Payload(payload),
+        }
+    }
+
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    /* What the closure panicked with, e.g. to continue the panic with 'resume_unwind'. */
+    pub fn into_payload(self) -> Box<dyn Any + Send + 'static> {
+        self.payload.0
+    }
+}
+
+impl Debug for ClosurePanicked {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClosurePanicked").field("message", &self.message).finish_non_exhaustive()
+    }
+}
+
+impl Display for ClosurePanicked {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.message() {
+            Some(message) => write!(f, "the update closure panicked: {message}"),
+            None => write!(f, "the update closure panicked"),
+        }
+    }
+}
+
+impl Error for ClosurePanicked {}
+
+/* Returned by 'try_fetch_update' and 'fetch_update_until' when they give up. Nothing was stored. */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetriesExhausted {
+    /* How many times the closure ran and its cas failed. */
+    pub attempts: usize,
+}
+
+impl Display for RetriesExhausted {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "gave up on the update after {} failed attempts", self.attempts)
+    }
+}
+
+impl Error for RetriesExhausted {}
+
+/* An index past the end of a collection. */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IndexOutOfBounds {
+    pub index: usize,
+    /* The length at the moment the index was checked. */
+    pub len: usize,
+}
+
+impl Display for IndexOutOfBounds {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "index {} is out of bounds for length {}", self.index, self.len)
+    }
+}
+
+impl Error for IndexOutOfBounds {}
+
+/* A key that isn't in the map. Reserved for 'MlcMap', which is still work in progress. */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyNotFound;
+
+impl Display for KeyNotFound {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "no such key in the map")
+    }
+}
+
+impl Error for KeyNotFound {}
@@ -1,3 +1,9 @@
-#![feature(unsafe_cell_from_mut)]
+#![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
+extern crate alloc;
+
+pub mod allocator;
+pub mod error;
+pub(crate) mod platform;
 pub mod primitives;
 pub mod collections;
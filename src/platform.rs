@@ -0,0 +1,30 @@
+/* The few things that work differently with and without the 'std' feature. */
+
+use crate::error::{ClosurePanicked, UpdateResult};
+
+pub(crate) fn catch<O>(func: impl FnOnce() -> O) -> UpdateResult<O> {
+    #[cfg(feature = "std")]
+    return std::panic::catch_unwind(std::panic::AssertUnwindSafe(func)).map_err(|panic| ClosurePanicked::new(panic).into());
+
+    #[cfg(not(feature = "std"))]
+    Ok(func())
+}
+
+/* Continues a panic that 'catch' stopped. */
+pub(crate) fn resume(panic: ClosurePanicked) -> ! {
+    #[cfg(feature = "std")]
+    std::panic::resume_unwind(panic.into_payload());
+
+    // 'catch' never catches anything without std.
+    #[cfg(not(feature = "std"))]
+    unreachable!("{:?}", panic)
+}
+
+/* Lets other threads run while we wait for one of them. Without std there's no scheduler to ask, so it only spins. */
+pub(crate) fn yield_now() {
+    #[cfg(feature = "std")]
+    std::thread::yield_now();
+
+    #[cfg(not(feature = "std"))]
+    core::hint::spin_loop();
+}
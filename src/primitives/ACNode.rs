@@ -0,0 +1,84 @@
+use crate::primitives::NodePool::{NodeHeap, Release};
+use crate::allocator::Allocator;
+use alloc::boxed::Box;
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{fence, AtomicBool, Ordering};
+
+/* ACNodes are only ever handled by the cells and their 'Reclaim'. The module is private, so outside the crate the type can't even be named.
+V is whatever the cell keeps per stored value, 'Arc<T>' for an 'AtomicCell<T>'. */
+pub struct ACNode<V> {
+    pub(crate) next: UnsafeCell<*mut Self>,
+    pub(crate) value: V,
+    pub(crate) chained_flag: AtomicBool,
+    // Only used by 'EpochReclaim': the epoch the node was retired in.
+    pub(crate) retired_epoch: UnsafeCell<usize>,
+    // Only used by 'AtomicCell': one more than the version of the node it replaced. Written before the node is published, read-only after.
+    pub(crate) version: u64,
+    // The 'NodeHeap' the node's memory goes back to once it's freed. Null for a plain Box, which is all a cell without an allocator or
+    // a pool ever makes.
+    pub(crate) heap: *const Release<V>,
+}
+
+impl<V> ACNode<V> {
+    pub(crate) fn new(value: V) -> *mut Self {
+        let correct_ptr = Box::into_raw(Box::new(MaybeUninit::<Self>::uninit())).cast::<Self>();
+        unsafe { Self::init(correct_ptr, value, core::ptr::null()) }
+    }
+
+    /* Like 'new', but the memory comes from 'heap' (its pool or its allocator). The node remembers the heap and returns to it when freed. */
+    pub(crate) fn new_in<A: Allocator>(value: V, heap: &NodeHeap<V, A>) -> *mut Self {
+        let correct_ptr = heap.allocate();
+        unsafe { Self::init(correct_ptr, value, (heap as *const NodeHeap<V, A>).cast::<Release<V>>()) }
+    }
+
+    unsafe fn init(correct_ptr: *mut Self, value: V, heap: *const Release<V>) -> *mut Self {
+        let false_ptr: *mut Self = core::ptr::null_mut(); // Avoids MaybeUninit
+
+        let pre = Self {
+            next: UnsafeCell::from(false_ptr),
+            value,
+            chained_flag: AtomicBool::new(false),
+            retired_epoch: UnsafeCell::new(0),
+            version: 0,
+            heap,
+        };
+
+        correct_ptr.write(pre);
+        *(*correct_ptr).next.get_mut() = correct_ptr; // next is now ptr to self on heap. Self is "leaked".
+
+        // TODO Check where used, might be redundant
+        fence(Ordering::Release);
+//      fence(Ordering::Release);
+        correct_ptr
+    }
+
+    /* Every ACNode ends here (or in 'into_value'), whichever 'Reclaim' decided it's time.
+    Safety: 'node' came from 'new'/'new_in' and nobody can reach it anymore. */
+    pub(crate) unsafe fn free(node: *mut Self) {
+        core::ptr::drop_in_place(&mut (*node).value);
+        Self::release(node);
+    }
+
+    /* Frees a node that was never published, handing back its value. Same safety as 'free'. */
+    pub(crate) unsafe fn into_value(node: *mut Self) -> V {
+        let value = core::ptr::read(&(*node).value);
+        Self::release(node);
+        value
+    }
+
+    /* The value is gone already, only the memory is left. */
+    unsafe fn release(node: *mut Self) {
+        let heap = (*node).heap;
+        if heap.is_null() {
+            Self::dealloc(node);
+        } else {
+            (*heap)(node);
+        }
+    }
+
+    /* Gives the memory of a node from 'new' back to the global allocator without dropping anything in it. */
+    unsafe fn dealloc(node: *mut Self) {
+        drop(Box::from_raw(node.cast::<MaybeUninit<Self>>()));
+    }
+}
@@ -1,8 +1,29 @@
-#[deny(clippy::pedantic)]
-use std::cell::UnsafeCell;
-use std::marker::PhantomData;
-use std::sync::atomic::{fence, AtomicBool, AtomicPtr, AtomicUsize, Ordering};
-use std::sync::Arc;
+use crate::primitives::ACNode::ACNode;
+use crate::primitives::Backoff::Backoff;
+use crate::primitives::NodePool::{NodeHeap, NodePool, NodeSource, PoolStats};
+use crate::allocator::{Allocator, Global};
+use crate::primitives::Reclaim::{CounterReclaim, Reclaim};
+use crate::platform;
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use core::marker::PhantomData;
+use core::mem::ManuallyDrop;
+use core::ops::{Add, BitAnd, BitOr, BitXor, Deref, Sub};
+use core::sync::atomic::{fence, AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+#[cfg(feature = "std")]
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::future::Future;
+#[cfg(feature = "std")]
+use core::pin::Pin;
+#[cfg(feature = "std")]
+use core::task::{Context, Poll, Waker};
+#[cfg(feature = "std")]
+use std::sync::{Condvar, Mutex, PoisonError};
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+use crate::error::{CasFailed, RetriesExhausted, UpdateError, UpdateResult};
 
 
 /* AtomicCell<T> simulates basic atomic operations on any type T. It mimics the behaviour of actual atomics:
@@ -20,28 +41,85 @@ Note that T may or may not be 'Copy'. To avoid extra allocation only an Arc<T> i
 return value is trivial. */
 
 
-pub struct AtomicCell<T> {
-    /* How many loads are currently in progress. After a load operation is finished it can decrement this value again.
-    Swaps do not load. */
-    load_counter: AtomicUsize,
-    /* An 'AtomicPtr' to the latest stored value of T. The 'ACNode<T>' contains the value and other important information for freeing memory.*/
+pub struct AtomicCell<T: ?Sized, R: Reclaim = CounterReclaim, A: Allocator = Global> {
+    /* Decides when replaced ACNodes are freed. Every access to an ACNode goes through it, see 'Reclaim'. */
+    reclaim: R,
+    /* An 'AtomicPtr' to the latest stored value of T. The 'ACNode' contains the value and other important information for freeing memory.*/
     // TODO Enforce Atomic Alignment
-    ptr: AtomicPtr<ACNode<T>>,
-    /* When 'AtomicCell<T>' is dropped then so is 'ACNode<T>' and hence some T. This has to be known by the compiler as
+    ptr: AtomicPtr<ACNode<Arc<T>>>,
+    /* When 'AtomicCell<T>' is dropped then so is 'ACNode' and hence some T. This has to be known by the compiler as
     'AtomicCell<T>' does - itself - not "hold" an instance of T */
-    _marker: PhantomData<ACNode<T>>,
+    _marker: PhantomData<ACNode<Arc<T>>>,
+    /* Wakes up threads waiting for the value to change. Without std nobody can wait. */
+    #[cfg(feature = "std")]
+    notifier: Notifier,
+    /* Where updaters that keep losing their cas line up, see 'fallback_after'. */
+    fallback: Fallback,
+    /* What a panic in a 'fetch_update' closure does, see 'panic_policy'. */
+    panics: PanicPolicy,
+    poisoned: AtomicBool,
+    /* Allocates and frees the ACNodes. Declared last: it must outlive every ACNode. */
+    nodes: NodeSource<Arc<T>, A>,
 }
 
-/* No assumptions about T is made. (As of right now it still need to be 'Sized') */
 impl<T> AtomicCell<T> {
     /* Simply constructs a new 'AtomicCell<T>', it obviously takes ownership of values. From creation to destruction there must ALWAYS
     be a valid T stored inside the AtomicCell. */
     pub fn new(value: T) -> Self {
+        Self::from_arc(Arc::new(value))
+    }
+}
+
+impl<T: ?Sized> AtomicCell<T> {
+    /* Same as 'new', but takes an already shared value. The cell simply holds one more reference to it, nothing is cloned or allocated
+    apart from the ACNode. */
+    pub fn from_arc(value: Arc<T>) -> Self {
+        Self::from_arc_with_reclaim(value, CounterReclaim::new())
+    }
+}
+
+impl<T: ?Sized, R: Reclaim> AtomicCell<T, R> {
+    pub fn from_arc_with_reclaim(value: Arc<T>, reclaim: R) -> Self {
+        Self::with_nodes(value, reclaim, NodeSource::Boxed(Global))
+    }
+}
+
+/* The '_in' constructors take the allocator the ACNodes are allocated with, e.g. an arena. Only the ACNodes come from it, nothing else:
+- the values are plain Arc<T>s, allocated by whoever made them ('new_in', 'store', ... use the global allocator for theirs),
+- the cell's own bookkeeping (the box that lets the ACNodes find the allocator, the node pool, the wakers) is on the global heap,
+- so is whatever T allocates itself, e.g. the buffer of an 'AtomicVec'. */
+impl<T, A: Allocator> AtomicCell<T, CounterReclaim, A> {
+    pub fn new_in(value: T, alloc: A) -> Self {
+        Self::from_arc_in(Arc::new(value), alloc)
+    }
+}
+
+impl<T: ?Sized, A: Allocator> AtomicCell<T, CounterReclaim, A> {
+    pub fn from_arc_in(value: Arc<T>, alloc: A) -> Self {
+        Self::from_arc_with_reclaim_in(value, CounterReclaim::new(), alloc)
+    }
+}
+
+/* No assumptions about T is made. Not even 'Sized': anything that fits in an Arc goes, e.g. 'AtomicCell<str>' or
+'AtomicCell<dyn Handler + Send + Sync>'. Those are built from an Arc<T> ('from_arc', 'store_arc', ...), as there is no owned unsized value to hand over. */
+impl<T: ?Sized, R: Reclaim, A: Allocator> AtomicCell<T, R, A> {
+    pub fn from_arc_with_reclaim_in(value: Arc<T>, reclaim: R, alloc: A) -> Self {
+        /* Boxed before the first ACNode points to it, moving the cell doesn't move the heap. */
+        Self::with_nodes(value, reclaim, NodeSource::Heap(Box::new(NodeHeap::new(alloc))))
+    }
+
+    fn with_nodes(value: Arc<T>, reclaim: R, nodes: NodeSource<Arc<T>, A>) -> Self {
         let cell = Self {
-            load_counter: AtomicUsize::new(0),
+            reclaim,
             /* ACNode::new() returns a pointer */
-            ptr: AtomicPtr::new(ACNode::new(value)),
+            ptr: AtomicPtr::new(nodes.node(value)),
             _marker: PhantomData,
+            #[cfg(feature = "std")]
+            notifier: Notifier::new(),
+            fallback: Fallback::new(DEFAULT_FALLBACK_AFTER),
+            panics: PanicPolicy::default(),
+            poisoned: AtomicBool::new(false),
+            nodes,
         };
 
         /* The ACNode contains a "chained flag" which marks whether a given ACNode is "chained" to its preceeding ACNodes.
@@ -61,223 +139,298 @@ impl<T> AtomicCell<T> {
         cell
     }
 
-    /* Takes a value of type T and stores it into the AtomicCell. Any loads and/or swaps that happen after a store will see only the latest value stored. */
-    pub fn store(&self, value: T) {
-        let to_acnode = ACNode::new(value);
+    /* Updates (everything that runs a closure and retries its cas until it gets through) are lock-free, but a single updater with a slow
+    closure can lose its cas forever against faster ones. With a fallback, after 'attempts' failed cas an updater takes a ticket and waits
+    for its turn instead. While anyone holds a ticket every other updater gets in line too, so the one whose turn it is competes with at most
+    one cas per thread that was already in flight, and every update completes. Once the line is empty everyone is back to plain cas.
+    Off by default ('DEFAULT_FALLBACK_AFTER' is 'usize::MAX').
+    Only the unbounded updates ('fetch_update', 'try_update', ...) ever wait in line. Stores and swaps don't run a
+    closure and never line up, and 'try_fetch_update' and 'fetch_update_until' never wait past their own limits: they keep to plain cas.
+    An update closure must not run another update on its own cell: once in line it would wait for its own turn. */
+    pub fn fallback_after(mut self, attempts: usize) -> Self {
+        self.fallback.after = attempts;
+        self
+    }
 
-        /* The AtomicPtr makes this operation atomic. Any future accesses now follow the new pointer to the new ACNode.
-        However some bookkeeping has to be done with the old ACNode. */
-        let old = self.ptr.swap(to_acnode, Ordering::AcqRel);
+    /* What happens when an update closure panics, see 'PanicPolicy'. Only the fetch_updates catch the panic, the other updates ('try_update',
+    'rcu', the arithmetic ones, ...) let it propagate under every policy. */
+    pub fn panic_policy(mut self, policy: PanicPolicy) -> Self {
+        self.panics = policy;
+        self
+    }
+
+    /* Whether a closure panicked under 'PanicPolicy::Poison' since the last 'clear_poison'. */
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned.load(Ordering::Acquire)
+    }
+
+    /* Lets the updates run again after a poisoning panic. Check (or store) the value first: it is whatever it was before the panic,
+    but the closure may have left other state of yours half done. */
+    pub fn clear_poison(&self) {
+        self.poisoned.store(false, Ordering::Release);
+    }
+
+    /* Freed ACNodes go back to a pool of up to 'cap' allocations that new ones are taken from, instead of to the allocator.
+    Worth it when the cell is written a lot. A cell can only get one pool, so this panics if called twice.
+    A cell without a pool (or an allocator, see 'new_in') keeps its ACNodes in plain Boxes and pays nothing for either. */
+    pub fn with_node_pool(mut self, cap: usize) -> Self {
+        /* ACNodes from a pool return to it when freed. Replacing the pool would leave them pointing at a dropped one. */
+        assert!(self.nodes.pool().is_none(), "the cell already has a node pool");
+        self.nodes.heap_mut().pool = Some(NodePool::new(cap));
+        self
+    }
+
+    /* Hits and misses of the node pool, None if the cell has none. */
+    pub fn pool_stats(&self) -> Option<PoolStats> {
+        self.nodes.pool().map(NodePool::stats)
+    }
+
+    /* The allocator the ACNodes come from. */
+    pub fn allocator(&self) -> &A {
+        self.nodes.alloc()
+    }
+
+    /* Every ACNode after the first comes from here, so it uses the allocator and the pool if there are any. */
+    fn new_node(&self, value: Arc<T>) -> *mut ACNode<Arc<T>> {
+        self.nodes.node(value)
+    }
+
+    /* The reclamation strategy of this cell, e.g. to look at how many nodes it is holding on to. */
+    pub fn reclaimer(&self) -> &R {
+        &self.reclaim
+    }
+
+    /* How many replaced ACNodes the cell is still holding on to. A number that keeps growing means something (usually a read that never
+    ends) is holding up reclamation. */
+    pub fn pending_nodes(&self) -> usize {
+        self.reclaim.pending()
+    }
+
+    /* Frees whatever can be freed right now without waiting. Returns how many ACNodes were freed. */
+    pub fn try_reclaim(&self) -> usize {
+        unsafe { self.reclaim.try_reclaim(&self.ptr) }
+    }
+
+    /* Waits until the ACNodes replaced so far can be freed and frees them. Returns how many ACNodes were freed.
+    With the default 'CounterReclaim' this waits for a moment in which no load is in progress, which may never come on a cell that is read constantly. */
+    pub fn reclaim_blocking(&self) -> usize {
+        unsafe { self.reclaim.reclaim_blocking(&self.ptr) }
+    }
+
+    /* The current value, if the cell holds the only reference to it. With '&mut self' nobody can load in between, so the replaced ACNodes
+    are freed first: one of them may still hold a clone of the same Arc. Returns None if the Arc is shared outside the cell. */
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        let latest = *self.ptr.get_mut();
 
-        /* This links the ACNode we just made to the old ACNode. Afterwards the new ACNode is considered "chained" because it points to it predecessor. */
         unsafe {
-            // This is safeguarded by the chained flag. The write becomes visible to other threads after a sync with the fence.
-            *(*to_acnode).next.get() = old;
-            // Drop the safeguard of .next TODO Relax?
-            (*to_acnode).chained_flag.store(true, Ordering::Release);
+            self.reclaim.drain(latest);
+            Arc::get_mut(&mut (*latest).value)
         }
+    }
 
-        /* Lastly it checks if freeing of memory can be done. */
-        // Communicate attempt at entering critical section
-        if self.load_counter.fetch_add(1, Ordering::AcqRel) == 0 {
-            unsafe {
-                self.free(to_acnode);
-            }
+    /* Takes the cell apart and hands back the current value, without cloning the Arc or going through a load. */
+    pub fn into_inner(mut self) -> Arc<T> {
+        // A null pointer tells 'drop' that the latest ACNode is gone already.
+        let latest = core::mem::replace(self.ptr.get_mut(), core::ptr::null_mut());
+
+        unsafe {
+            self.reclaim.drain(latest);
+            ACNode::into_value(latest)
         }
-        // TODO Release?
-        self.load_counter.fetch_sub(1, Ordering::Release);
+    }
+
+    /* Takes an Arc<T> and stores it into the AtomicCell. Any loads and/or swaps that happen after a store will see only the latest value stored.
+    The Arc may already be shared elsewhere, nothing is cloned. */
+    pub fn store_arc(&self, value: Arc<T>) {
+        self.replace(value, |_| ());
     }
 
     /* Loading is a very simple task. It simply follows the 'AtomicPtr' and reads the value stored in the current ACNode. Loads will always only get the latest value. */
     pub fn load(&self) -> Arc<T> {
-        /* Marks that we perform a load operation. Since a thread may be stuck between getting the ptr and derefing it no frees must happen while a load is in progress.
+        /* Marks that we perform a load operation. No frees of this ACNode must happen while a load is in progress.
         Otherwise the ACNode may be removed from under our feet. */
-        self.load_counter.fetch_add(1, Ordering::AcqRel);
-
-//        self.load_counter.fetch_add(1, Ordering::AcqRel);
-        // Load visibly happens after the fetch_add
-        let latest = self.ptr.load(Ordering::Acquire);
+        let (latest, token) = self.reclaim.protect(&self.ptr);
 
-//        let latest = self.ptr.load(Ordering::Acquire);
         /* .value of the ACNode stores an Arc */
         let ret_val = unsafe { (*latest).value.clone() };
 
-        /* Again, free memory if possible. And mark the load operation as completed. */
-        // fetch_sub happens visibly after the load
-        
-        // Only access is us:
-        if self.load_counter.load(Ordering::Acquire) == 1 {
-            unsafe{ self.free(latest )}
-        }
-        // TODO Release?
-        self.load_counter.fetch_sub(1, Ordering::Release);
-        
+        /* Mark the load operation as completed. */
+        unsafe { self.reclaim.unprotect(latest, token) };
+
         ret_val
     }
 
-    // TODO MAKE THIS AND STORE TAKE AN ARC<T> instead of T
-    /* Swap resembles a store operation. In addition if also follows the "old-pointer" to its predecessor to get its value.
-    Swaps always return the value they replaced. */
-    pub fn swap(&self, value: T) -> Arc<T> {
-        let to_acnode = ACNode::new(value);
+    /* A load that never touches the Arc's reference count. The guard keeps the ACNode (and therefore the value) protected instead, so it
+    cannot be freed while the guard is alive. Keep guards short-lived: with the default 'CounterReclaim' no memory is freed while one exists. */
+    pub fn load_guard(&self) -> AtomicCellGuard<'_, T, R, A> {
+        let (latest, token) = self.reclaim.protect(&self.ptr);
 
-        // AcqRel makes sure we get the latest, still "in use" ptr and make our ptr the "in use" new one
-        let old = self.ptr.swap(to_acnode, Ordering::AcqRel);
+        AtomicCellGuard {
+            cell: self,
+            node: latest,
+            token: ManuallyDrop::new(token),
+        }
+    }
 
-//        let old = self.ptr.swap(to_acnode, Ordering::AcqRel);
+    /* Closure form of 'load_guard'. The reference cannot escape the closure, which makes it hard to hold on to a guard for too long. */
+    pub fn with<O, F>(&self, func: F) -> O
+    where
+        F: FnOnce(&T) -> O,
+    {
+        func(&self.load_guard())
+    }
 
-        let ret_val;
-        unsafe {
+    /* Swap resembles a store operation. In addition it returns the value it replaced, read while the replaced ACNode is still protected. */
+    pub fn swap_arc(&self, value: Arc<T>) -> Arc<T> {
+        self.replace(value, Arc::clone)
+    }
 
-            // Because a node visible as "in use" is never deallocated, and we are the only ones with access to this node as not "in use", it must still exist.
-            ret_val = (*old).value.clone(); // Simply gets the old ACNode's value.
+    /* Stores and swaps are a cas loop rather than a plain 'AtomicPtr::swap': every ACNode carries the version of its predecessor plus one,
+    which has to be known before the node is published. 'with_old' gets to look at the replaced value while it is still protected. */
+    fn replace<O>(&self, value: Arc<T>, with_old: impl Fn(&Arc<T>) -> O) -> O {
+        let to_acnode = self.new_node(value);
 
-            // Connect the new ACNode to the older one.
-            *(*to_acnode).next.get() = old;
-            (*to_acnode).chained_flag.store(true, Ordering::Release);
-//            (*to_acnode).chained_flag.store(true, Ordering::Release);
-        }
+        /* Nothing runs between the load and the cas, so a store only loses against a cas that landed in that short window. It doesn't
+        line up: it would have to wait behind slow closures. */
+        loop {
+            let (old, token) = self.reclaim.protect(&self.ptr);
 
-        
-        /* Lastly it checks if freeing of memory can be done. */
-        // Communicate attempt at entering critical section
-        if self.load_counter.fetch_add(1, Ordering::AcqRel) == 0 {
-            unsafe {
-                self.free(to_acnode);
+            if let Ok(ret_val) = unsafe { self.publish(old, token, to_acnode, &with_old) } {
+                return ret_val;
             }
         }
-        // TODO Release?
-        self.load_counter.fetch_sub(1, Ordering::Release);
+    }
+
+    /* Every write ends here. Tries to replace 'old' by 'to_new' and ends the protection of 'old' either way.
+    On success 'with_old' gets to look at the replaced value while it is still protected, then the old ACNode is retired and waiters are woken up.
+    On failure nothing happened: 'to_new' is still the caller's, to try again with or to free.
+    Safety: 'old' and 'token' come from the same 'protect', 'to_new' was never published. */
+    unsafe fn publish<O>(
+        &self,
+        old: *mut ACNode<Arc<T>>,
+        token: R::Token,
+        to_new: *mut ACNode<Arc<T>>,
+        with_old: impl FnOnce(&Arc<T>) -> O,
+    ) -> Result<O, ()> {
+        (*to_new).version = (*old).version + 1;
+
+        /* Any future accesses now follow the new pointer to the new ACNode. However some bookkeeping has to be done with the old ACNode. */
+        if self.cas(old, to_new).is_err() {
+            self.reclaim.unprotect(old, token);
+            return Err(());
+        }
+        let ret_val = with_old(&(*old).value);
+
+        /* Nobody can reach the old ACNode through the cell anymore, hand it over for freeing. Only after our own protection is gone, or the
+        reclaimer would have to keep it around for us. Only we can retire it, so it stays alive until then. */
+        self.reclaim.unprotect(old, token);
+        self.reclaim.retire(to_new, old);
+        self.notify();
+        Ok(ret_val)
+    }
+
+    /* Like 'load', but also returns the version of the value. Every value stored into the cell gets the version of the one it replaced plus one,
+    starting at 0 on construction. Storing an equal value (or even the same Arc) again still bumps it. */
+    pub fn load_versioned(&self) -> (Arc<T>, u64) {
+        let (latest, token) = self.reclaim.protect(&self.ptr);
+        let ret_val = unsafe { ((*latest).value.clone(), (*latest).version) };
+        unsafe { self.reclaim.unprotect(latest, token) };
 
         ret_val
     }
 
-    /* This function performs heavy logic to free memory. It is best understood after reading the implementation of ACNode.
-    It is marked as unsafe since it uses a raw pointer argument and requires that no threads hold pointers to the given ACNodes predecessors!
-    -> Guaranteed by load_counter.
-    Not public! */
-    unsafe fn free(&self, latest: *mut ACNode<T>) {
-        /* Remember the "chained flag" of ACNode? It signals whether an ACNode is fully initialized. To perform any operation we "unchain" the ACNode
-        thereby guranteering that is was chained and that no other thread can operate on it. */
-        
-        // @MIRI: SEEMS LIKE MULTIPLE THREADS CAN ARRIVE HERE, CAUSING A DATA RACE
-        // FOUND ERROR: CHECKING FOR LOAD COUNTER == 0, then stall: => THREAD 1 has a pointer to a node (ptr1) with a free(ptr1) pending;
-        // THREAD 2 COMES IN AND READS THE LOAD COUNTER == 0, wanting to dealloc too => during dealloc it removes *ptr1 => race
-        match (*latest).chained_flag.compare_exchange(
-            true,
-            false,
-            Ordering::AcqRel,
-            // TODO Should be correct
-            Ordering::Relaxed,
-        ) {
-            /* If the cas on the first ACNode succeeds we can proceed...
-            Remember: "latest" is the pointer to the very first ("latest") ACNode. */
-            Ok(_) => {
-                /* Think of the following code as walking down the nodes of a linked list. There are 3 pointers involved:
-                - latest -> the very first pointer (head).
-                - prev_next_ptr -> the pointer with which we arrived at the ACNode we are currently at.
-                - next_next_ptr -> the pointer from the ACNode are at to another ACNode. This pointer will later replace prev_next_pointer and so on... */
-                let mut next_next_ptr: *mut ACNode<T> = *(*latest).next.get();
-
-                /* Checks if the latest ACNode is self-referential. Self-reference marks some "end" in the list.*/
-                if !(next_next_ptr == latest)
-                // First node is not self-ref.
-                {
-                    /* Now we go one ACNode deep
-
-                                         |
-                                         | (latest)
-                                         |
-                        ---------    ----------
-                        -       <-----        -
-                        --------- |  ----------
-                              prev_next_ptr ( old next_next_ptr)
-                    */
-                    let mut prev_next_ptr = next_next_ptr.clone();
-
-                    /* Now entering a loop. Note that this loop is finite. (We always make progress, no extra iterations can be created.) */
-                    loop {
-                        // prev_next_ptr is the read ptr from the previous iteration;
-                        match (*prev_next_ptr).chained_flag.compare_exchange(
-                            true,
-                            false,
-                            Ordering::AcqRel,
-                            // TODO Should be correct could leak mem otherwise
-                            Ordering::Relaxed,
-//                            Ordering::AcqRel,
-//                            Ordering::Acquire,
-                        ) {
-                            // Read the next ptr of the node
-                            // Note: We never deref next_next_ptr! Only as prev_next_ptr in the following iteration!
-                            Ok(_) => {
-                                next_next_ptr = *(*prev_next_ptr).next.get();
-
-                                if next_next_ptr == prev_next_ptr {
-                                    // This node is self-referential. Drop it! As it was the last node, we are done.
-                                    let drop_this = Box::from_raw(prev_next_ptr);
-                                    // TODO Remove, have this be implicit
-                                    drop(drop_this); // gonna be explicit here :)
-                                                     // Make the first node self-ref, to mark as end.
-                                    // let dst = &mut (*latest).next as *mut *mut ACNode<T>;
-                                    let dst = (*latest).next.get();
-                                    let write_this = latest;
-                                    // write_volatile(dst, write_this);
-                                    *(dst) = write_this;
-                                    (*latest).chained_flag.store(true, Ordering::Release);
-                                    break;
-                                } else {
-                                    // This node has a next. Drop this node and proceed with its next ptr.
-                                    let drop_this = Box::from_raw(prev_next_ptr);
-                                    // TODO Remove, have this be implicit
-                                    drop(drop_this); // gonna be explicit here :)
-                                    prev_next_ptr = next_next_ptr;
-                                }
-                            }
-                            Err(_) => {
-                                // This node is not chained, we cant drop it and we cant proceed. Therefore we "bridge" to it for future frees. Then we are done.
-                                *(*latest).next.get() = prev_next_ptr;
-
-//                                fence(Ordering::AcqRel);
-                                (*latest).chained_flag.store(true, Ordering::Release);
-
-//                                (*latest).chained_flag.store(true, Ordering::Release);
-                                break;
-                            }
-                        }
-                        /*
-                        Follow the previous next ptr.
-                        Check if the new ACNode is chained. If not, end.
-                        Check if the new ACNode is self-referential. If so, then its final, dealloc it and then end.
-                        Otherwise, if its neiter the final nor (the first, checked outside of loop nor) not init, dealloc it. And repeat again using its next-ptr
-                        in the next iteration.
-                        */
-                    }
-                } else {
-                    // Is self-ref, hit undo.
-                    (*latest).chained_flag.store(true, Ordering::Release);
-//                    (*latest).chained_flag.store(true, Ordering::Release);
+    /* The version of the current value, see 'load_versioned'. Cheap enough to check whether a cached value is stale. */
+    pub fn version(&self) -> u64 {
+        let (latest, token) = self.reclaim.protect(&self.ptr);
+        let version = unsafe { (*latest).version };
+        unsafe { self.reclaim.unprotect(latest, token) };
+
+        version
+    }
+
+    /* Stores 'new' only if the cell is still at 'expected_version'. On success the replaced value is returned, the cell is then at
+    'expected_version + 1'. On failure the rejected 'new' and the version actually stored are handed back. Unlike 'compare_exchange'
+    this cannot be fooled by a value that was replaced and stored again. */
+    pub fn compare_exchange_version(&self, expected_version: u64, new: Arc<T>) -> Result<Arc<T>, CasFailed<Arc<T>, u64>> {
+        let to_new = self.new_node(new);
+
+        loop {
+            let (latest, token) = self.reclaim.protect(&self.ptr);
+
+            unsafe {
+                let actual = (*latest).version;
+                if actual != expected_version {
+                    self.reclaim.unprotect(latest, token);
+                    // Never published, so nobody else knows about this node.
+                    let rejected = ACNode::into_value(to_new);
+                    return Err(CasFailed { current: actual, rejected });
                 }
+
+                if let Ok(previous) = self.publish(latest, token, to_new, Arc::clone) {
+                    return Ok(previous);
+                }
+                // Replaced in between, so the version moved on. Looking again reports it.
             }
+        }
+    }
+
+    /* Called after every publish. */
+    fn notify(&self) {
+        #[cfg(feature = "std")]
+        self.notifier.notify();
+    }
 
-            /* If the cas failed than some other thread is working on it, freeing the memory for us. Great! We are done. */
-            Err(_) => (),
+    /* Blocks until the cell holds something other than 'seen' and returns that. Returns right away if it already does.
+    "Other" means another Arc (Arc::ptr_eq), so storing an equal value wakes waiters up, storing the very same Arc again does not. */
+    #[cfg(feature = "std")]
+    pub fn wait_changed(&self, seen: &Arc<T>) -> Arc<T> {
+        if let Some(current) = self.changed_from(seen) {
+            return current;
         }
+
+        self.notifier
+            .wait(|| self.changed_from(seen), None)
+            .expect("waits without a timeout only return once changed")
     }
 
+    /* Same as 'wait_changed', but gives up after 'timeout'. Returns None if nothing changed in time. */
+    #[cfg(feature = "std")]
+    pub fn wait_changed_timeout(&self, seen: &Arc<T>, timeout: Duration) -> Option<Arc<T>> {
+        if let Some(current) = self.changed_from(seen) {
+            return Some(current);
+        }
 
-    // TODO Inline this
-    pub(crate) unsafe fn phantom_double_load(&self) -> (Arc<T>, *mut ACNode<T>) {
-        let latest = self.ptr.load(Ordering::Acquire);
-//        let latest = self.ptr.load(Ordering::Acquire);
-        /* .value of the ACNode stores an Arc */
-        let ret_val = unsafe { (*latest).value.clone() };
-        (ret_val, latest)
+        self.notifier.wait(|| self.changed_from(seen), Some(Instant::now() + timeout))
+    }
+
+    /* The async version of 'wait_changed'. The future resolves once the cell holds something other than 'seen'. It only needs a waker,
+    so it runs on any executor. */
+    #[cfg(feature = "std")]
+    pub fn changed<'a>(&'a self, seen: &'a Arc<T>) -> Changed<'a, T, R, A> {
+        Changed {
+            cell: self,
+            seen,
+            slot: None,
+        }
+    }
+
+    /* The current value, if it is not 'seen'. Only clones the Arc if it changed. */
+    #[cfg(feature = "std")]
+    fn changed_from(&self, seen: &Arc<T>) -> Option<Arc<T>> {
+        let guard = self.load_guard();
+        let current = unsafe { &(*guard.node).value };
+
+        if Arc::ptr_eq(current, seen) {
+            None
+        } else {
+            Some(current.clone())
+        }
     }
 
     pub(crate) unsafe fn cas(
         &self,
-        expected: *mut ACNode<T>,
-        new: *mut ACNode<T>,
+        expected: *mut ACNode<Arc<T>>,
+        new: *mut ACNode<Arc<T>>,
     ) -> Result<(), ()> {
 
         match self
@@ -291,166 +444,688 @@ impl<T> AtomicCell<T> {
         }
     }
 
+    /* Compares by identity rather than by value: 'new' is only stored if 'current' is the very Arc<T> the cell holds right now (Arc::ptr_eq).
+    On success the replaced value is returned. On failure both the rejected 'new' and the value actually stored are handed back ('CasFailed'),
+    so the caller can retry without cloning anything. */
+    pub fn compare_exchange(&self, current: &Arc<T>, new: Arc<T>) -> Result<Arc<T>, CasFailed<Arc<T>>> {
+        let to_new = self.new_node(new);
+
+        loop {
+            let (latest, token) = self.reclaim.protect(&self.ptr);
+
+            unsafe {
+                if !Arc::ptr_eq(&(*latest).value, current) {
+                    let actual = (*latest).value.clone();
+                    self.reclaim.unprotect(latest, token);
+                    // Never published, so nobody else knows about this node.
+                    let rejected = ACNode::into_value(to_new);
+                    return Err(CasFailed { current: actual, rejected });
+                }
+
+                if let Ok(previous) = self.publish(latest, token, to_new, Arc::clone) {
+                    return Ok(previous);
+                }
+                /* The node was replaced between the load and the cas. The new one may still hold 'current' (think store_arc of the same Arc),
+                so look again instead of failing spuriously. */
+            }
+        }
+    }
+
     /// Reads an Arc<T> and stores an Arc<T>. No other thread is guarenteed to have made a store in between the read and store.
     /// O is the (optional) output of the closure.
-    // TODO: ATTEMPT FREE
-    pub fn fetch_update<O, F>(&self, mut func: F) -> std::thread::Result<O>
+    pub fn fetch_update<O, F>(&self, func: F) -> UpdateResult<O>
     where
         // Can be FnMut, but it's probably a logic error for you (if it isn't also Fn)
         F: FnMut(Arc<T>) -> (Arc<T>, O),
     {
+        self.fetch_update_with(func, true, |_| true)
+    }
+
+    /* 'fetch_update' that gives up after 'max_attempts' failed cas, waiting according to 'backoff' in between. The closure is called at most
+    'max_attempts' times, so with 0 it is never called. Giving up is 'UpdateError::RetriesExhausted', panics are reported like 'fetch_update' does. */
+    pub fn try_fetch_update<O, F>(
+        &self,
+        max_attempts: usize,
+        backoff: Backoff,
+        func: F,
+    ) -> UpdateResult<O>
+    where
+        F: FnMut(Arc<T>) -> (Arc<T>, O),
+    {
+        if max_attempts == 0 {
+            self.check_poison()?;
+            return Err(RetriesExhausted { attempts: 0 }.into());
+        }
+
+        self.fetch_update_with(func, false, |failed| {
+            if failed >= max_attempts {
+                return false;
+            }
+            backoff.snooze(failed);
+            true
+        })
+    }
+
+    /* 'fetch_update' that gives up once 'deadline' has passed, waiting according to 'backoff' in between. The closure is always called at
+    least once, a deadline in the past means a single attempt. Sleeps are cut short so they never end after the deadline. */
+    #[cfg(feature = "std")]
+    pub fn fetch_update_until<O, F>(
+        &self,
+        deadline: Instant,
+        backoff: Backoff,
+        func: F,
+    ) -> UpdateResult<O>
+    where
+        F: FnMut(Arc<T>) -> (Arc<T>, O),
+    {
+        self.fetch_update_with(func, false, |failed| {
+            if Instant::now() >= deadline {
+                return false;
+            }
+            backoff.snooze_until(failed, deadline);
+            Instant::now() < deadline
+        })
+    }
+
+    /* Every update that runs a closure starts here. A poisoned cell refuses, see 'PanicPolicy::Poison'. */
+    fn check_poison(&self) -> UpdateResult<()> {
+        if self.is_poisoned() {
+            return Err(UpdateError::Poisoned);
+        }
+        Ok(())
+    }
+
+    /* Runs an update closure. Under 'PanicPolicy::Poison' a panic poisons the cell on its way out: by the drop guard, which is forgotten if
+    'func' returns. Works without std, where nothing catches the panic. */
+    fn run<O>(&self, func: impl FnOnce() -> O) -> O {
+        let poison = (self.panics == PanicPolicy::Poison).then(|| PoisonOnPanic(&self.poisoned));
+        let output = func();
+        core::mem::forget(poison);
+        output
+    }
+
+    /* 'run' for the fetch_updates, which also catch the panic unless the policy is 'Propagate'. */
+    fn catch<O>(&self, func: impl FnOnce() -> O) -> UpdateResult<O> {
+        platform::catch(|| self.run(func)).map_err(|error| match (self.panics, error) {
+            (PanicPolicy::Propagate, UpdateError::Panicked(panic)) => platform::resume(panic),
+            (_, error) => error,
+        })
+    }
+
+    /* The loop behind all fetch_updates. After every failed cas 'retry' gets the number of failed attempts so far and decides whether to go again.
+    Only updaters with 'line_up' set use the fallback: waiting in line can take any time, a bounded updater would overrun its limit. */
+    fn fetch_update_with<O, F, G>(&self, mut func: F, line_up: bool, mut retry: G) -> UpdateResult<O>
+    where
+        F: FnMut(Arc<T>) -> (Arc<T>, O),
+        G: FnMut(usize) -> bool,
+    {
+        self.check_poison()?;
+
+        let mut failed = 0;
+        // Dropping it (also on the way out of a panic) hands the turn to the next in line.
+        let mut turn = None;
+
         loop {
-            self.load_counter.fetch_add(1, Ordering::AcqRel);
-//            self.load_counter.fetch_add(1, Ordering::AcqRel);
-            let (arg, ptr) = unsafe { self.phantom_double_load() };
-
-            let (write, output) =
-                // Not my problem if your function panics, (and if its only FnMut fucks up some invariant of yours). I won't let it block the free mechanism of the AtomicCell.
-                match std::panic::catch_unwind(std::panic::AssertUnwindSafe(||func(arg))) {
-                    Ok(tuple) => tuple,
-                    Err(panic_message) => {
-                        // Prevents the "block"
-                        // TODO Release?
-                        self.load_counter.fetch_sub(1, Ordering::Release);
-//                        self.load_counter.fetch_sub(1, Ordering::AcqRel);
-                        return Err(panic_message);
-                    }
-                };
+            if line_up {
+                self.fallback.line_up(&mut turn, failed);
+            }
+
+            /* The ACNode stays protected until the cas is done. Otherwise it could be freed and its address reused by a newer ACNode,
+            and the cas would succeed against a value we never saw. The guard unprotects it on every way out, a panic unwinding
+            through here included, so a panicking closure never blocks the free mechanism. */
+            let guard = self.load_guard();
+            let arg = unsafe { (*guard.node).value.clone() };
+
+            // Not my problem if your function panics, (and if its only FnMut fucks up some invariant of yours).
+            let (write, output) = self.catch(|| func(arg))?;
 
-            let to_new = ACNode::new_from_arc(write);
+            let to_new = self.new_node(write);
 
             // Bash the output of the func against the AtomicCell until it works
             unsafe {
-                match self.cas(ptr, to_new) {
-                    Ok(_) => {
-                        *(*to_new).next.get() = ptr;
-//                        fence(Ordering::AcqRel);
-                        (*to_new).chained_flag.store(true, Ordering::Release);
-
-//                        (*to_new).chained_flag.store(true, Ordering::Release);
-                        // TODO Release?
-                        self.load_counter.fetch_sub(1, Ordering::Release);
-//                        self.load_counter.fetch_sub(1, Ordering::AcqRel);
-                        return Ok(output);
+                let (ptr, token) = guard.into_parts();
+                match self.publish(ptr, token, to_new, |_| ()) {
+                    Ok(()) => return Ok(output),
+                    Err(()) => {
+                        // TODO Remove, have this be implicit
+                        ACNode::free(to_new);
                     }
-                    Err(_) => {
-                        // TODO Release?
-                        self.load_counter.fetch_sub(1, Ordering::Release);
+                }
+            }
 
-//                        self.load_counter.fetch_sub(1, Ordering::AcqRel);
+            // Not protecting anything while backing off.
+            failed += 1;
+            if !retry(failed) {
+                return Err(RetriesExhausted { attempts: failed }.into());
+            }
+        }
+    }
+}
 
-                        // TODO Remove, have this be implicit
-                        drop(Box::from_raw(to_new));
-                        continue;
+impl<T, R: Reclaim> AtomicCell<T, R> {
+    /* Like 'new', but with a reclamation strategy other than the default, e.g. 'AtomicCell::with_reclaim(value, EpochReclaim::new())'. */
+    pub fn with_reclaim(value: T, reclaim: R) -> Self {
+        Self::from_arc_with_reclaim(Arc::new(value), reclaim)
+    }
+}
+
+impl<T, R: Reclaim, A: Allocator> AtomicCell<T, R, A> {
+    pub fn with_reclaim_in(value: T, reclaim: R, alloc: A) -> Self {
+        Self::from_arc_with_reclaim_in(Arc::new(value), reclaim, alloc)
+    }
+
+    /* Takes a value of type T and stores it into the AtomicCell. See 'store_arc'. */
+    pub fn store(&self, value: T) {
+        self.store_arc(Arc::new(value));
+    }
+
+    /* Same as 'swap_arc'. */
+    pub fn swap(&self, value: T) -> Arc<T> {
+        self.swap_arc(Arc::new(value))
+    }
+
+    /* 'into_inner', unwrapped from its Arc if nobody else holds a reference to it. Otherwise the Arc is handed back. */
+    pub fn try_into_owned(self) -> Result<T, Arc<T>> {
+        Arc::try_unwrap(self.into_inner())
+    }
+
+    /* Leaves 'T::default()' behind and returns what was there, like 'mem::take'. */
+    pub fn take(&self) -> Arc<T>
+    where
+        T: Default,
+    {
+        self.swap(T::default())
+    }
+
+    /* Shaped like 'AtomicU64::fetch_update', so code written against std atomics ports over mechanically: 'func' gets the current value and
+    returns the next one, or None to give up. Returns Ok(previous value) once the new one is stored, Err(current value) if 'func' gave up.
+    'func' may be called several times if other threads store in between. A panic in 'func' simply propagates. The outer Result is
+    Err('UpdateError::Poisoned') if the cell is poisoned, 'func' isn't called then. */
+    pub fn try_update<F>(&self, func: F) -> UpdateResult<Result<Arc<T>, Arc<T>>>
+    where
+        F: FnMut(&T) -> Option<T>,
+    {
+        self.update_with(func, false)
+    }
+
+    /* Same as 'try_update', but returns the newly stored value instead of the previous one. */
+    pub fn update_and_fetch<F>(&self, func: F) -> UpdateResult<Result<Arc<T>, Arc<T>>>
+    where
+        F: FnMut(&T) -> Option<T>,
+    {
+        self.update_with(func, true)
+    }
+
+    /* Read-copy-update: 'func' gets a private copy of the current value to mutate in place, which is then stored. Returns what 'func' returned.
+    Saves the clone-mutate-wrap dance around 'fetch_update'. The copy is made with 'Arc::make_mut': the first attempt has to clone T, as the
+    cell still holds the current value. If the cas fails the copy was never published, so it is still ours alone and the next attempt
+    overwrites it ('clone_from') instead of allocating a new one. 'func' may be called several times, a panic in it simply propagates.
+    Err only if the cell is poisoned. */
+    pub fn rcu<O, F>(&self, mut func: F) -> UpdateResult<O>
+    where
+        T: Clone,
+        F: FnMut(&mut T) -> O,
+    {
+        self.check_poison()?;
+
+        let mut turn = None;
+        let mut failed = 0;
+        let mut scratch: Option<Arc<T>> = None;
+
+        loop {
+            self.fallback.line_up(&mut turn, failed);
+
+            // See 'update_with'.
+            let guard = self.load_guard();
+            let current = unsafe { &(*guard.node).value };
+            // Cloning T runs code of yours too.
+            let (next, output) = self.run(|| {
+                let mut next = match scratch.take() {
+                    Some(mut next) => {
+                        Arc::make_mut(&mut next).clone_from(current);
+                        next
                     }
+                    None => current.clone(),
+                };
+                let output = func(Arc::make_mut(&mut next));
+                (next, output)
+            });
+
+            let to_new = self.new_node(next);
+
+            unsafe {
+                let (old, token) = guard.into_parts();
+                if self.publish(old, token, to_new, |_| ()).is_ok() {
+                    return Ok(output);
                 }
+
+                // Never published, the scratch copy is reused.
+                scratch = Some(ACNode::into_value(to_new));
             }
+            failed += 1;
         }
     }
+
+    /* 'try_update' that returns the previous value either way: if 'func' gives up, the current value simply stays. */
+    fn fetch_with<F>(&self, func: F) -> UpdateResult<Arc<T>>
+    where
+        F: FnMut(&T) -> Option<T>,
+    {
+        self.update_with(func, false).map(|done| done.unwrap_or_else(|current| current))
+    }
+
+    /* Returns the previous value on success, or the new one with 'fetch_new'. */
+    fn update_with<F>(&self, mut func: F, fetch_new: bool) -> UpdateResult<Result<Arc<T>, Arc<T>>>
+    where
+        F: FnMut(&T) -> Option<T>,
+    {
+        self.check_poison()?;
+
+        let mut turn = None;
+        let mut failed = 0;
+
+        loop {
+            self.fallback.line_up(&mut turn, failed);
+
+            /* The guard keeps the ACNode protected through the cas (see 'fetch_update'), and unprotects it even if 'func' panics. */
+            let guard = self.load_guard();
+            let Some(new) = self.run(|| func(&guard)) else {
+                return Ok(Err(unsafe { (*guard.node).value.clone() }));
+            };
+
+            let new = Arc::new(new);
+            let to_new = self.new_node(new.clone());
+
+            unsafe {
+                let (old, token) = guard.into_parts();
+                if let Ok(previous) = self.publish(old, token, to_new, Arc::clone) {
+                    return Ok(Ok(if fetch_new { new } else { previous }));
+                }
+
+                // Never published.
+                ACNode::free(to_new);
+            }
+            failed += 1;
+        }
+    }
+}
+
+/* The 'AtomicU64' shorthands, for any T with the matching operator. All of them return the previous value and retry like 'try_update',
+Err only if the cell is poisoned (the operator is user code like any closure).
+Overflow is whatever the operator does for T (a panic in debug builds for the integers), not the wrap-around of the std atomics. */
+impl<T: Clone + Add<Output = T>, R: Reclaim, A: Allocator> AtomicCell<T, R, A> {
+    pub fn fetch_add(&self, val: T) -> UpdateResult<Arc<T>> {
+        self.fetch_with(|x| Some(x.clone() + val.clone()))
+    }
 }
 
-// Deprecate? TODO: ATTEMPT FREE
-impl<T: Eq> AtomicCell<T> {
-    pub fn cas_by_eq(&self, expected: &T, new: T) -> Result<(), ()> {
-        let to_new = ACNode::new(new);
+impl<T: Clone + Sub<Output = T>, R: Reclaim, A: Allocator> AtomicCell<T, R, A> {
+    pub fn fetch_sub(&self, val: T) -> UpdateResult<Arc<T>> {
+        self.fetch_with(|x| Some(x.clone() - val.clone()))
+    }
+}
+
+/* Unlike the std atomics these don't store anything if the cell already holds the larger (smaller) value, so the version stays the same. */
+impl<T: Clone + Ord, R: Reclaim, A: Allocator> AtomicCell<T, R, A> {
+    pub fn fetch_max(&self, val: T) -> UpdateResult<Arc<T>> {
+        self.fetch_with(|x| (val > *x).then(|| val.clone()))
+    }
 
-        self.load_counter.fetch_add(1, Ordering::AcqRel);
-        let latest = self.ptr.load(Ordering::Acquire);
+    pub fn fetch_min(&self, val: T) -> UpdateResult<Arc<T>> {
+        self.fetch_with(|x| (val < *x).then(|| val.clone()))
+    }
+}
 
-        
+impl<T: Clone + BitAnd<Output = T>, R: Reclaim, A: Allocator> AtomicCell<T, R, A> {
+    pub fn fetch_and(&self, val: T) -> UpdateResult<Arc<T>> {
+        self.fetch_with(|x| Some(x.clone() & val.clone()))
+    }
+}
+
+impl<T: Clone + BitOr<Output = T>, R: Reclaim, A: Allocator> AtomicCell<T, R, A> {
+    pub fn fetch_or(&self, val: T) -> UpdateResult<Arc<T>> {
+        self.fetch_with(|x| Some(x.clone() | val.clone()))
+    }
+}
+
+impl<T: Clone + BitXor<Output = T>, R: Reclaim, A: Allocator> AtomicCell<T, R, A> {
+    pub fn fetch_xor(&self, val: T) -> UpdateResult<Arc<T>> {
+        self.fetch_with(|x| Some(x.clone() ^ val.clone()))
+    }
+}
+
+// Deprecate?
+impl<T: Eq, R: Reclaim, A: Allocator> AtomicCell<T, R, A> {
+    /* Stores 'new' if the current value equals 'expected'. On failure the value found instead and the rejected 'new' are handed back,
+    the latter as the T it came in as. The outer Result is Err('UpdateError::Poisoned') if the cell is poisoned ('eq' is code of yours). */
+    pub fn cas_by_eq(&self, expected: &T, new: T) -> UpdateResult<Result<(), CasFailed<T, Arc<T>>>> {
+        self.check_poison()?;
+        let to_new = self.new_node(Arc::new(new));
+
+        // The guard unprotects the ACNode if 'eq' panics.
+        let guard = self.load_guard();
 
         unsafe {
-            if *(*latest).value == *expected {
-                match self.cas(latest, to_new) {
-                    Ok(_) => {
-                        *(*to_new).next.get() = latest;
-                        fence(Ordering::AcqRel);
-                        (*to_new).chained_flag.store(true, Ordering::Release);
-                        self.load_counter.fetch_sub(1, Ordering::AcqRel);
-                        return Ok(());
-                    }
-                    Err(_) => (),
+            let current = if self.run(|| *guard == *expected) {
+                let (latest, token) = guard.into_parts();
+                match self.publish(latest, token, to_new, |_| ()) {
+                    Ok(()) => return Ok(Ok(())),
+                    // Whatever replaced it in between.
+                    Err(()) => self.load(),
                 }
+            } else {
+                (*guard.node).value.clone()
             };
-            self.load_counter.fetch_sub(1, Ordering::AcqRel);
-            drop(Box::from_raw(to_new));
+            // Never published, so its Arc is still ours alone.
+            let Ok(rejected) = Arc::try_unwrap(ACNode::into_value(to_new)) else {
+                unreachable!("an unpublished ACNode's value is not shared");
+            };
+            Ok(Err(CasFailed { current, rejected }))
         }
-        Err(())
     }
 }
 
-impl<T> Drop for AtomicCell<T> {
+impl<T: ?Sized, R: Reclaim, A: Allocator> Drop for AtomicCell<T, R, A> {
     fn drop(&mut self) {
         // No reference to AtomicCell exists, since its dropping.
-        self.load(); // Drops all but the current ACNode (Load counter = 0) => call to free, can be optimised
-
-        let latest = self.ptr.load(Ordering::Acquire);
+        let latest = *self.ptr.get_mut();
+        // Taken apart by 'into_inner' already.
+        if latest.is_null() {
+            return;
+        }
 
-//        let latest = self.ptr.load(Ordering::Acquire);
         unsafe {
+            // Drops all but the current ACNode.
+            self.reclaim.drain(latest);
+
             // Manually drop the latest node.
-            let boxed_last_node = Box::from_raw(latest);
-            drop(boxed_last_node);
+            ACNode::free(latest);
+        }
+    }
+}
+
+/* Returned by 'AtomicCell::load_guard'. Dereferences to the value that was the latest when the guard was created. */
+pub struct AtomicCellGuard<'a, T: ?Sized, R: Reclaim = CounterReclaim, A: Allocator = Global> {
+    cell: &'a AtomicCell<T, R, A>,
+    node: *mut ACNode<Arc<T>>,
+    // Handed back to the reclaimer on drop.
+    token: ManuallyDrop<R::Token>,
+}
+
+impl<T: ?Sized, R: Reclaim, A: Allocator> Deref for AtomicCellGuard<'_, T, R, A> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // The protection taken in load_guard keeps the node alive.
+        unsafe { &(*self.node).value }
+    }
+}
+
+impl<T: ?Sized, R: Reclaim, A: Allocator> AtomicCellGuard<'_, T, R, A> {
+    /* Takes over the protection, e.g. to hand it to 'publish'. The node stays protected until the token goes back to the reclaimer. */
+    fn into_parts(self) -> (*mut ACNode<Arc<T>>, R::Token) {
+        let mut guard = ManuallyDrop::new(self);
+        let token = unsafe { ManuallyDrop::take(&mut guard.token) };
+        (guard.node, token)
+    }
+}
+
+impl<T: ?Sized, R: Reclaim, A: Allocator> Drop for AtomicCellGuard<'_, T, R, A> {
+    fn drop(&mut self) {
+        /* Same as the end of a load: mark the load operation as completed. */
+        unsafe {
+            let token = ManuallyDrop::take(&mut self.token);
+            self.cell.reclaim.unprotect(self.node, token);
+        }
+    }
+}
+
+/* Returned by 'AtomicCell::changed'. */
+#[cfg(feature = "std")]
+pub struct Changed<'a, T: ?Sized, R: Reclaim = CounterReclaim, A: Allocator = Global> {
+    cell: &'a AtomicCell<T, R, A>,
+    seen: &'a Arc<T>,
+    // Where our waker is registered, see 'Notifier::register'.
+    slot: Option<(u64, usize)>,
+}
+
+#[cfg(feature = "std")]
+impl<T: ?Sized, R: Reclaim, A: Allocator> Future for Changed<'_, T, R, A> {
+    type Output = Arc<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Arc<T>> {
+        let this = self.get_mut();
+
+        if let Some(current) = this.cell.changed_from(this.seen) {
+            return Poll::Ready(current);
+        }
+
+        // Look again after registering, a publish in between would have found no waker to wake.
+        match this.cell.notifier.register(cx.waker(), &mut this.slot, || this.cell.changed_from(this.seen)) {
+            Some(current) => Poll::Ready(current),
+            None => Poll::Pending,
+        }
+    }
+}
+
+/* A future dropped before the next publish (a timeout, a select that went the other way) takes its waker with it. Otherwise the waker
+would stay registered until some publish, and every publish until then would take the lock for nobody. */
+#[cfg(feature = "std")]
+impl<T: ?Sized, R: Reclaim, A: Allocator> Drop for Changed<'_, T, R, A> {
+    fn drop(&mut self) {
+        if let Some(slot) = self.slot.take() {
+            self.cell.notifier.unregister(slot);
         }
     }
 }
 
 // Requires T: Send: If T is not Send but Clone, then it could be unsafely transferred between threads via AtomicCell.
-unsafe impl<T: Send> Send for AtomicCell<T> {}
+unsafe impl<T: ?Sized + Send, R: Reclaim, A: Allocator + Send> Send for AtomicCell<T, R, A> {}
 // Don't do Sync kids. It's bad for your (mental) health.
-unsafe impl<T: Send + Sync> Sync for AtomicCell<T> {}
+// The allocator is used from every thread that stores.
+unsafe impl<T: ?Sized + Send + Sync, R: Reclaim, A: Allocator + Sync> Sync for AtomicCell<T, R, A> {}
+
+
+/* How many failed cas an updater puts up with before it lines up, see 'AtomicCell::fallback_after'. Never, unless asked for. */
+pub const DEFAULT_FALLBACK_AFTER: usize = usize::MAX;
 
+/* A ticket lock: first come, first served, so nobody waits forever behind luckier threads. Only updaters that lost too often
+(or that see others in line) ever touch it. */
+struct Fallback {
+    after: usize,
+    next_ticket: AtomicUsize,
+    now_serving: AtomicUsize,
+}
 
-struct ACNode<T> {
-    next: UnsafeCell<*mut Self>,
-    value: Arc<T>,
-    chained_flag: AtomicBool,
+/* Holding one means it's our turn. Dropping it serves the next ticket. */
+struct Turn<'a> {
+    fallback: &'a Fallback,
 }
 
-impl<T> ACNode<T> {
-    pub fn new(value: T) -> *mut Self {
-        let false_ptr: *mut Self = std::ptr::null_mut(); // Avoids MaybeUninit
+impl Fallback {
+    fn new(after: usize) -> Self {
+        Self {
+            after,
+            next_ticket: AtomicUsize::new(0),
+            now_serving: AtomicUsize::new(0),
+        }
+    }
 
-        let pre = Self {
-            next: UnsafeCell::from(false_ptr),
-            value: Arc::new(value),
-            chained_flag: AtomicBool::new(false),
-        };    
-        
-        // Box::into_raw(Box::new(x))
-        let x = Box::into_raw(Box::new(UnsafeCell::new(pre)));
+    /* Called before every attempt. Takes a ticket and waits for its turn if 'failed' is too high or somebody is already in line.
+    Once 'turn' is set it stays until the update is done. */
+    fn line_up<'a>(&'a self, turn: &mut Option<Turn<'a>>, failed: usize) {
+        if turn.is_some() {
+            return;
+        }
 
-        unsafe {
-            let correct_ptr = (*x).get();
-            * (*correct_ptr).next.get_mut() = correct_ptr; // next is now ptr to self on heap. Self is "leaked".
-//      fence(Ordering::Release);
-        correct_ptr
+        // SeqCst so an updater taking a ticket and one checking the line agree on who came first.
+        let queued = self.next_ticket.load(Ordering::SeqCst) != self.now_serving.load(Ordering::SeqCst);
+        if failed < self.after && !queued {
+            return;
+        }
+
+        let ticket = self.next_ticket.fetch_add(1, Ordering::SeqCst);
+        let mut waited = 0;
+        while self.now_serving.load(Ordering::Acquire) != ticket {
+            // The turn before ours may run a slow closure, don't burn the core waiting for it.
+            waited += 1;
+            if waited < 64 {
+                core::hint::spin_loop();
+            } else {
+                platform::yield_now();
+            }
+        }
+
+        *turn = Some(Turn { fallback: self });
+    }
+}
+
+impl Drop for Turn<'_> {
+    fn drop(&mut self) {
+        self.fallback.now_serving.fetch_add(1, Ordering::Release);
+    }
+}
+
+/* Parks threads and wakes tasks until a publish happens. Publishing only has to check an atomic counter if nobody is waiting. */
+#[cfg(feature = "std")]
+struct Notifier {
+    /* Blocked threads plus registered wakers. */
+    waiters: AtomicUsize,
+    wakers: Mutex<Wakers>,
+    condvar: Condvar,
+}
+
+/* Wakers registered since the last publish. Every publish wakes all of them and starts a new generation. A slot is None once its
+future was dropped, the indices of the others must not move. */
+#[cfg(feature = "std")]
+struct Wakers {
+    generation: u64,
+    list: Vec<Option<Waker>>,
+}
+
+#[cfg(feature = "std")]
+impl Notifier {
+    fn new() -> Self {
+        Self {
+            waiters: AtomicUsize::new(0),
+            wakers: Mutex::new(Wakers {
+                generation: 0,
+                list: Vec::new(),
+            }),
+            condvar: Condvar::new(),
+        }
+    }
+
+    /* Called after every publish. */
+    fn notify(&self) {
+        // Pairs with the fences in wait and register: either the waiter sees the new ACNode, or we see the waiter.
+        fence(Ordering::SeqCst);
+        if self.waiters.load(Ordering::Relaxed) > 0 {
+            /* Taking the lock makes sure a waiter is either still about to check (and will see the new ACNode) or already waiting. */
+            let woken = {
+                let mut wakers = self.wakers.lock().unwrap_or_else(PoisonError::into_inner);
+                wakers.generation += 1;
+                core::mem::take(&mut wakers.list)
+            };
+            self.waiters.fetch_sub(woken.iter().flatten().count(), Ordering::Relaxed);
+            self.condvar.notify_all();
+            woken.into_iter().flatten().for_each(Waker::wake);
         }
-    } 
+    }
 
-   fn new_from_arc(value: Arc<T>) -> *mut Self{
-        let false_ptr: *mut Self = std::ptr::null_mut(); // Avoids MaybeUninit
+    /* Waits until 'check' returns something or the deadline passes. */
+    fn wait<O>(&self, mut check: impl FnMut() -> Option<O>, deadline: Option<Instant>) -> Option<O> {
+        self.waiters.fetch_add(1, Ordering::Relaxed);
+        fence(Ordering::SeqCst);
 
-        let pre = Self {
-            next: UnsafeCell::from(false_ptr),
-            value: value,
-            chained_flag: AtomicBool::new(false),
+        let mut guard = self.wakers.lock().unwrap_or_else(PoisonError::into_inner);
+        let result = loop {
+            if let Some(output) = check() {
+                break Some(output);
+            }
+
+            guard = match deadline {
+                None => self.condvar.wait(guard).unwrap_or_else(PoisonError::into_inner),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        break None;
+                    }
+                    self.condvar
+                        .wait_timeout(guard, deadline - now)
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .0
+                }
+            };
         };
+        drop(guard);
 
-        
-        //TODO: Check *mut *pre;
-        let x = Box::into_raw(Box::new(UnsafeCell::new(pre)));
-        
-        
-        unsafe {
-            let correct_ptr = (*x).get();
+        self.waiters.fetch_sub(1, Ordering::Relaxed);
+        result
+    }
 
-           * (*correct_ptr).next.get_mut() = correct_ptr; // next is now ptr to self on heap. Self is "leaked".
-        
-        // TODO Check where used, might be redundant
-        fence(Ordering::Release);
-//      fence(Ordering::Release);
-        correct_ptr
+    /* The async counterpart of 'wait'. Registers 'waker' for the next publish unless 'check' already returns something.
+    'slot' remembers where the waker went, so polling again before the next publish replaces it instead of piling up wakers. */
+    fn register<O>(&self, waker: &Waker, slot: &mut Option<(u64, usize)>, check: impl FnOnce() -> Option<O>) -> Option<O> {
+        let mut wakers = self.wakers.lock().unwrap_or_else(PoisonError::into_inner);
+
+        match *slot {
+            Some((generation, index)) if generation == wakers.generation => {
+                // Only our own drop empties our slot.
+                if let Some(registered) = &mut wakers.list[index] {
+                    registered.clone_from(waker);
+                }
+            }
+            _ => {
+                self.waiters.fetch_add(1, Ordering::Relaxed);
+                *slot = Some((wakers.generation, wakers.list.len()));
+                wakers.list.push(Some(waker.clone()));
+            }
+        }
+        fence(Ordering::SeqCst);
+
+        check()
+    }
+
+    /* Takes back a waker 'register' left, unless a publish already took it. */
+    fn unregister(&self, (generation, index): (u64, usize)) {
+        let mut wakers = self.wakers.lock().unwrap_or_else(PoisonError::into_inner);
+        if generation != wakers.generation {
+            return;
+        }
+        if wakers.list[index].take().is_some() {
+            self.waiters.fetch_sub(1, Ordering::Relaxed);
+        }
+        // Trailing empty slots belong to nobody, the next registration may as well reuse them.
+        while let Some(None) = wakers.list.last() {
+            wakers.list.pop();
         }
     }
 }
+
+/* What a panic in an update closure does to the cell, see 'AtomicCell::panic_policy'. The panic never leaves anything behind in the
+cell itself: the value stays what it was and nothing stays protected. But an FnMut closure may have broken invariants of its own.
+
+Propagate       | the panic goes on unwinding through the update (resume_unwind)
+ReturnErr       | the fetch_updates return it as Err, the default
+Poison          | like ReturnErr, and every later update that runs a closure returns Err('UpdateError::Poisoned') without calling it,
+                | until 'clear_poison'
+
+Only the fetch_updates catch panics, the other updates ('try_update', 'rcu', ...) let them go on unwinding under every policy. They still
+poison the cell on the way out under 'Poison'. Without std a panic can't be caught: it always unwinds (or aborts), whatever the policy. */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PanicPolicy {
+    Propagate,
+    #[default]
+    ReturnErr,
+    Poison,
+}
+
+/* Poisons the cell if dropped, see 'AtomicCell::run'. */
+struct PoisonOnPanic<'a>(&'a AtomicBool);
+
+impl Drop for PoisonOnPanic<'_> {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Release);
+    }
+}
@@ -0,0 +1,110 @@
+use crate::primitives::NoUninit::NoUninit;
+use crate::primitives::AtomicSeqCell::{put_word, seq_copy_out, seq_lock, seq_read, seq_try_lock, seq_unlock, seq_write, word_of};
+use crate::error::UpdateResult;
+use crate::platform;
+use core::marker::PhantomData;
+use core::mem::{size_of, MaybeUninit};
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+/* AtomicCopyCell<T> is the 'AtomicCell<T>' for small Copy types. There is no ACNode and no Arc: the value itself is kept in the cell.
+
+                     |              |                |
+AtomicCell<u32>      | .store(u32)  | .load()        | .swap(u32)           allocates an ACNode and an Arc per store
+                     |  u32 -> ()   |    -> Arc<u32> |   u32 -> Arc<u32>
+--------------------------------------------------------------------
+                     |              |                |
+AtomicCopyCell<u32>  | .store(u32)  | .load()        | .swap(u32)           never allocates
+                     |  u32 -> ()   |    -> u32      |   u32 -> u32
+
+Up to 8 bytes the value simply is an AtomicU64. Up to 16 bytes it is spread over two words guarded by a sequence counter, the seqlock of
+'AtomicSeqCell' without its heap allocation. Anything larger doesn't compile, use 'AtomicSeqCell'.
+T is copied bytewise into the words, so it must not have padding: see 'NoUninit', which the primitives and arrays of them implement. */
+pub struct AtomicCopyCell<T: NoUninit> {
+    /* Only used above 8 bytes. Odd while a store is writing the words. */
+    seq: AtomicUsize,
+    words: [AtomicU64; 2],
+    _marker: PhantomData<T>,
+}
+
+impl<T: NoUninit> AtomicCopyCell<T> {
+    // Fits a single AtomicU64, no seqlock needed.
+    const NATIVE: bool = size_of::<T>() <= 8;
+
+    pub fn new(value: T) -> Self {
+        const { assert!(size_of::<T>() <= 16, "AtomicCopyCell holds at most 16 bytes, use AtomicSeqCell") };
+
+        Self {
+            seq: AtomicUsize::new(0),
+            words: [AtomicU64::new(word_of(&value, 0)), AtomicU64::new(word_of(&value, 1))],
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn load(&self) -> T {
+        if Self::NATIVE {
+            return from_word(self.words[0].load(Ordering::Acquire));
+        }
+        seq_read(&self.seq, &self.words).0
+    }
+
+    pub fn store(&self, value: T) {
+        self.swap(value);
+    }
+
+    pub fn swap(&self, value: T) -> T {
+        if Self::NATIVE {
+            return from_word(self.words[0].swap(word_of(&value, 0), Ordering::AcqRel));
+        }
+
+        let seq = seq_lock(&self.seq);
+        let old = seq_copy_out(&self.words);
+        seq_write(&self.words, &value);
+        seq_unlock(&self.seq, seq);
+        old
+    }
+
+    /* Same as 'AtomicCell::fetch_update', with T in place of Arc<T>. Nothing is protected while 'func' runs, so there's nothing to block.
+    The new value is only stored if nobody stored in between. */
+    pub fn fetch_update<O, F>(&self, mut func: F) -> UpdateResult<O>
+    where
+        F: FnMut(T) -> (T, O),
+    {
+        loop {
+            if Self::NATIVE {
+                let bits = self.words[0].load(Ordering::Acquire);
+                let (new, output) = platform::catch(|| func(from_word(bits)))?;
+
+                // Compares bits, not values: 0.0 and -0.0 differ, a NaN equals itself.
+                if self.words[0]
+                    .compare_exchange(bits, word_of(&new, 0), Ordering::AcqRel, Ordering::Relaxed)
+                    .is_ok()
+                {
+                    return Ok(output);
+                }
+            } else {
+                let (current, seq) = seq_read::<T>(&self.seq, &self.words);
+                let (new, output) = platform::catch(|| func(current))?;
+
+                // Only succeeds if the sequence didn't move on since 'current' was read.
+                if seq_try_lock(&self.seq, seq) {
+                    seq_write(&self.words, &new);
+                    seq_unlock(&self.seq, seq);
+                    return Ok(output);
+                }
+            }
+        }
+    }
+}
+
+impl<T: NoUninit + Default> Default for AtomicCopyCell<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+fn from_word<T: NoUninit>(word: u64) -> T {
+    let mut out = MaybeUninit::uninit();
+    put_word(&mut out, 0, word);
+    // Only called for T of at most 8 bytes, all of them written.
+    unsafe { out.assume_init() }
+}
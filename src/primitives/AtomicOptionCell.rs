@@ -0,0 +1,169 @@
+use crate::primitives::ACNode::ACNode;
+use crate::primitives::Reclaim::{CounterReclaim, Reclaim};
+use alloc::sync::Arc;
+use core::marker::PhantomData;
+use core::sync::atomic::{fence, AtomicPtr, Ordering};
+
+/* AtomicOptionCell<T> is an 'AtomicCell<T>' that may hold nothing. "No value yet" and "slot emptied" don't need an
+'AtomicCell<Option<T>>' (and with it an extra allocation for every Option) anymore:
+
+                      |              |                      |
+AtomicCell<T>         | .store(T)    | .load() -> Arc<T>    | .swap(T) -> Arc<T>
+--------------------------------------------------------------------
+                      |              |                      |
+AtomicOptionCell<T>   | .store(T)    | .load()              | .swap(T)           | .take()
+                      |              |   -> Option<Arc<T>>  |   -> Option<...>   |   -> Option<Arc<T>>
+
+The ACNodes and their reclamation are exactly the ones of 'AtomicCell', an empty cell simply points to an ACNode holding 'None'.
+Like 'AtomicCell', T may be unsized. */
+pub struct AtomicOptionCell<T: ?Sized, R: Reclaim = CounterReclaim> {
+    reclaim: R,
+    ptr: AtomicPtr<ACNode<Option<Arc<T>>>>,
+    _marker: PhantomData<ACNode<Option<Arc<T>>>>,
+}
+
+impl<T> AtomicOptionCell<T> {
+    pub fn new(value: Option<T>) -> Self {
+        Self::from_arc(value.map(Arc::new))
+    }
+}
+
+impl<T: ?Sized> AtomicOptionCell<T> {
+    pub fn from_arc(value: Option<Arc<T>>) -> Self {
+        Self::from_arc_with_reclaim(value, CounterReclaim::new())
+    }
+}
+
+impl<T: ?Sized, R: Reclaim> AtomicOptionCell<T, R> {
+    pub fn from_arc_with_reclaim(value: Option<Arc<T>>, reclaim: R) -> Self {
+        let cell = Self {
+            reclaim,
+            ptr: AtomicPtr::new(ACNode::new(value)),
+            _marker: PhantomData,
+        };
+
+        // The first ACNode is chained, see 'AtomicCell::new'.
+        unsafe {
+            (*(cell.ptr.load(Ordering::Relaxed)))
+                .chained_flag
+                .store(true, Ordering::Relaxed);
+        }
+        fence(Ordering::Release);
+        cell
+    }
+
+    pub fn load(&self) -> Option<Arc<T>> {
+        let (latest, token) = self.reclaim.protect(&self.ptr);
+        let ret_val = unsafe { (*latest).value.clone() };
+        unsafe { self.reclaim.unprotect(latest, token) };
+
+        ret_val
+    }
+
+    /* Only looks at the ACNode, the Arc (if any) is never touched. */
+    pub fn is_none(&self) -> bool {
+        let (latest, token) = self.reclaim.protect(&self.ptr);
+        let none = unsafe { (*latest).value.is_none() };
+        unsafe { self.reclaim.unprotect(latest, token) };
+
+        none
+    }
+
+    pub fn is_some(&self) -> bool {
+        !self.is_none()
+    }
+
+    pub fn store_arc(&self, value: Arc<T>) {
+        self.publish(Some(value));
+    }
+
+    pub fn swap_arc(&self, value: Arc<T>) -> Option<Arc<T>> {
+        self.publish(Some(value))
+    }
+
+    /* Empties the cell and returns what it held. Taking from an empty cell doesn't allocate. */
+    pub fn take(&self) -> Option<Arc<T>> {
+        if self.is_none() {
+            return None;
+        }
+        self.publish(None)
+    }
+
+    /* Same as 'AtomicCell::swap_arc'. */
+    fn publish(&self, value: Option<Arc<T>>) -> Option<Arc<T>> {
+        let to_acnode = ACNode::new(value);
+        let old = self.ptr.swap(to_acnode, Ordering::AcqRel);
+
+        unsafe {
+            let ret_val = (*old).value.clone();
+            self.reclaim.retire(to_acnode, old);
+            ret_val
+        }
+    }
+}
+
+impl<T, R: Reclaim> AtomicOptionCell<T, R> {
+    pub fn with_reclaim(value: Option<T>, reclaim: R) -> Self {
+        Self::from_arc_with_reclaim(value.map(Arc::new), reclaim)
+    }
+
+    pub fn store(&self, value: T) {
+        self.publish(Some(Arc::new(value)));
+    }
+
+    pub fn swap(&self, value: T) -> Option<Arc<T>> {
+        self.publish(Some(Arc::new(value)))
+    }
+
+    /* Stores 'value' only if the cell is empty. Otherwise the value is handed back untouched. */
+    pub fn store_if_empty(&self, value: T) -> Result<(), T> {
+        let to_new = ACNode::new(Some(Arc::new(value)));
+
+        loop {
+            let (latest, token) = self.reclaim.protect(&self.ptr);
+
+            unsafe {
+                if (*latest).value.is_some() {
+                    self.reclaim.unprotect(latest, token);
+                    // Never published, so the Arc is still ours alone.
+                    let rejected = ACNode::into_value(to_new).and_then(Arc::into_inner);
+                    return Err(rejected.expect("unpublished value is unique"));
+                }
+
+                if self
+                    .ptr
+                    .compare_exchange(latest, to_new, Ordering::AcqRel, Ordering::Relaxed)
+                    .is_ok()
+                {
+                    // Unprotect first, see 'AtomicCell::publish'.
+                    self.reclaim.unprotect(latest, token);
+                    self.reclaim.retire(to_new, latest);
+                    return Ok(());
+                }
+
+                // Someone else got in between. Maybe the cell is still empty, look again.
+                self.reclaim.unprotect(latest, token);
+            }
+        }
+    }
+}
+
+impl<T> Default for AtomicOptionCell<T> {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+impl<T: ?Sized, R: Reclaim> Drop for AtomicOptionCell<T, R> {
+    fn drop(&mut self) {
+        let latest = *self.ptr.get_mut();
+
+        unsafe {
+            self.reclaim.drain(latest);
+            ACNode::free(latest);
+        }
+    }
+}
+
+unsafe impl<T: ?Sized + Send, R: Reclaim> Send for AtomicOptionCell<T, R> {}
+unsafe impl<T: ?Sized + Send + Sync, R: Reclaim> Sync for AtomicOptionCell<T, R> {}
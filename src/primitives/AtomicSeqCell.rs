@@ -0,0 +1,177 @@
+use crate::error::UpdateResult;
+use crate::platform;
+use crate::primitives::NoUninit::NoUninit;
+use alloc::boxed::Box;
+use core::hint::spin_loop;
+use core::marker::PhantomData;
+use core::mem::{size_of, MaybeUninit};
+use core::ptr;
+use core::sync::atomic::{fence, AtomicU64, AtomicUsize, Ordering};
+
+/* AtomicSeqCell<T> is for Copy values too large for 'AtomicCopyCell' (think 64 to 256 byte telemetry structs). Stores write the value in
+place, nothing is allocated after construction. Loads copy it out optimistically and retry if a store ran at the same time, so they never
+block a store and never see half of one. Stores take turns.
+
+                     |              |                |
+AtomicSeqCell<T>     | .store(T)    | .load()        | .swap(T)
+                     |  T -> ()     |    -> T        |   T -> T
+
+Readers busy-retry while a store is running, so this suits values that are read far more often than written. Like 'AtomicCopyCell',
+T is copied bytewise into the words and must not have padding, see 'NoUninit'. A struct with mixed field widths usually has some:
+order its fields by size, or make the gaps explicit fields, before implementing 'NoUninit' for it. */
+pub struct AtomicSeqCell<T: NoUninit> {
+    /* Even when nobody is storing, odd while a store writes the words. Bumped by 2 per store. */
+    seq: AtomicUsize,
+    /* T, 8 bytes per word. The last word is zero-padded. */
+    words: Box<[AtomicU64]>,
+    _marker: PhantomData<T>,
+}
+
+impl<T: NoUninit> AtomicSeqCell<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            seq: AtomicUsize::new(0),
+            words: (0..words_for::<T>()).map(|i| AtomicU64::new(word_of(&value, i))).collect(),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn load(&self) -> T {
+        seq_read(&self.seq, &self.words).0
+    }
+
+    pub fn store(&self, value: T) {
+        let seq = seq_lock(&self.seq);
+        seq_write(&self.words, &value);
+        seq_unlock(&self.seq, seq);
+    }
+
+    pub fn swap(&self, value: T) -> T {
+        let seq = seq_lock(&self.seq);
+        let old = seq_copy_out(&self.words);
+        seq_write(&self.words, &value);
+        seq_unlock(&self.seq, seq);
+        old
+    }
+
+    /* Same as 'AtomicCell::fetch_update', with T in place of Arc<T>. The new value is only stored if no store happened since 'func' got
+    its copy, otherwise 'func' runs again on a fresh one. */
+    pub fn fetch_update<O, F>(&self, mut func: F) -> UpdateResult<O>
+    where
+        F: FnMut(T) -> (T, O),
+    {
+        loop {
+            let (current, seq) = seq_read::<T>(&self.seq, &self.words);
+            let (new, output) = platform::catch(|| func(current))?;
+
+            if seq_try_lock(&self.seq, seq) {
+                seq_write(&self.words, &new);
+                seq_unlock(&self.seq, seq);
+                return Ok(output);
+            }
+        }
+    }
+}
+
+impl<T: NoUninit + Default> Default for AtomicSeqCell<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+/* How many u64 words it takes to hold a T. */
+pub(crate) fn words_for<T>() -> usize {
+    size_of::<T>().div_ceil(8)
+}
+
+/* The i-th 8 bytes of 'value', zero-padded past its end. All of them are initialized, that's what 'NoUninit' is for. */
+pub(crate) fn word_of<T: NoUninit>(value: &T, i: usize) -> u64 {
+    let mut word = 0u64;
+    let start = i * 8;
+    if start < size_of::<T>() {
+        let len = (size_of::<T>() - start).min(8);
+        unsafe {
+            ptr::copy_nonoverlapping((value as *const T).cast::<u8>().add(start), (&mut word as *mut u64).cast::<u8>(), len);
+        }
+    }
+    word
+}
+
+/* Writes 'word' as the i-th 8 bytes of 'out'. */
+pub(crate) fn put_word<T: NoUninit>(out: &mut MaybeUninit<T>, i: usize, word: u64) {
+    let start = i * 8;
+    if start < size_of::<T>() {
+        let len = (size_of::<T>() - start).min(8);
+        unsafe {
+            ptr::copy_nonoverlapping((&word as *const u64).cast::<u8>(), out.as_mut_ptr().cast::<u8>().add(start), len);
+        }
+    }
+}
+
+/* The seqlock. The words are only ever accessed atomically, so a reader racing a writer reads a mix of old and new words (and throws them
+away) but never causes a data race. The fences are the ones from Boehm's "Can Seqlocks Get Along with Programming Language Memory Models?". */
+
+/* Retries until it read all the words without a store in between. Returns the value and the (even) sequence it was read at. */
+pub(crate) fn seq_read<T: NoUninit>(seq: &AtomicUsize, words: &[AtomicU64]) -> (T, usize) {
+    loop {
+        let before = seq.load(Ordering::Acquire);
+        if before & 1 == 1 {
+            spin_loop();
+            continue;
+        }
+
+        let mut out = MaybeUninit::uninit();
+        for (i, word) in words.iter().enumerate().take(words_for::<T>()) {
+            put_word(&mut out, i, word.load(Ordering::Relaxed));
+        }
+
+        fence(Ordering::Acquire);
+        if seq.load(Ordering::Relaxed) == before {
+            // Every byte of T came from a word written by a complete store.
+            return (unsafe { out.assume_init() }, before);
+        }
+    }
+}
+
+/* Reads the words while holding the lock, nobody can write in between. */
+pub(crate) fn seq_copy_out<T: NoUninit>(words: &[AtomicU64]) -> T {
+    let mut out = MaybeUninit::uninit();
+    for (i, word) in words.iter().enumerate().take(words_for::<T>()) {
+        put_word(&mut out, i, word.load(Ordering::Relaxed));
+    }
+    unsafe { out.assume_init() }
+}
+
+/* Waits for the running store (if any) and makes the sequence odd. Returns the even sequence it started from. */
+pub(crate) fn seq_lock(seq: &AtomicUsize) -> usize {
+    loop {
+        let current = seq.load(Ordering::Relaxed);
+        if current & 1 == 0 && seq_try_lock(seq, current) {
+            return current;
+        }
+        spin_loop();
+    }
+}
+
+/* Makes the sequence odd, but only if it still is 'expected'. */
+pub(crate) fn seq_try_lock(seq: &AtomicUsize, expected: usize) -> bool {
+    if seq
+        .compare_exchange(expected, expected.wrapping_add(1), Ordering::Acquire, Ordering::Relaxed)
+        .is_err()
+    {
+        return false;
+    }
+    // Readers that see any of the following word stores also see the odd sequence.
+    fence(Ordering::Release);
+    true
+}
+
+pub(crate) fn seq_write<T: NoUninit>(words: &[AtomicU64], value: &T) {
+    for (i, word) in words.iter().enumerate().take(words_for::<T>()) {
+        word.store(word_of(value, i), Ordering::Relaxed);
+    }
+}
+
+pub(crate) fn seq_unlock(seq: &AtomicUsize, locked_at: usize) {
+    seq.store(locked_at.wrapping_add(2), Ordering::Release);
+}
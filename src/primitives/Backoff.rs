@@ -0,0 +1,69 @@
+use crate::platform;
+use core::hint::spin_loop;
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+/* What an updater does after its cas failed and before it tries again, see 'AtomicCell::try_fetch_update'.
+
+Spin            | busy-waits, twice as long after every failure (capped)  | cheapest when updates are short and cores plentiful
+Yield           | gives the rest of its time slice to another thread      | when there are more updaters than cores
+Exponential     | sleeps 'initial', then twice that, ... up to 'max'      | when the closure is slow and contention heavy
+
+Without std there is nobody to yield to or sleep with: Yield only spins once and Exponential spins like Spin. The variants are the
+same either way, so code that names one builds with and without the 'std' feature. */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Backoff {
+    #[default]
+    Spin,
+    Yield,
+    Exponential { initial: Duration, max: Duration },
+}
+
+// 2^6 spin_loop hints at most, more only burns the core.
+const SPIN_LIMIT: u32 = 6;
+
+impl Backoff {
+    /* 'failed' counts the failed attempts so far, starting at 1. */
+    pub(crate) fn snooze(&self, failed: usize) {
+        self.pause(failed, None);
+    }
+
+    /* Same as 'snooze', but never waits past 'deadline'. */
+    #[cfg(feature = "std")]
+    pub(crate) fn snooze_until(&self, failed: usize, deadline: Instant) {
+        self.pause(failed, Some(deadline));
+    }
+
+    #[cfg_attr(not(feature = "std"), allow(unused_variables))]
+    fn pause(&self, failed: usize, deadline: Option<Deadline>) {
+        let exp = u32::try_from(failed.saturating_sub(1)).unwrap_or(u32::MAX);
+
+        match *self {
+            Backoff::Spin => spin(exp),
+            Backoff::Yield => platform::yield_now(),
+            #[cfg(feature = "std")]
+            Backoff::Exponential { initial, max } => {
+                let mut pause = initial.saturating_mul(2u32.saturating_pow(exp)).min(max);
+                if let Some(deadline) = deadline {
+                    pause = pause.min(deadline.saturating_duration_since(Instant::now()));
+                }
+                std::thread::sleep(pause);
+            }
+            #[cfg(not(feature = "std"))]
+            Backoff::Exponential { .. } => spin(exp),
+        }
+    }
+}
+
+fn spin(exp: u32) {
+    for _ in 0..1u32 << exp.min(SPIN_LIMIT) {
+        spin_loop();
+    }
+}
+
+// There are no deadlines without a clock.
+#[cfg(feature = "std")]
+type Deadline = Instant;
+#[cfg(not(feature = "std"))]
+type Deadline = core::convert::Infallible;
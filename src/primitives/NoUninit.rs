@@ -0,0 +1,62 @@
+/* The Copy types 'AtomicCopyCell' and 'AtomicSeqCell' can hold. They keep T as plain u64 words, so every byte of a T has to be an
+initialized byte: reading a padding byte into a u64 is undefined behaviour, not just garbage. And a pointer that went through a u64 has
+lost its provenance, it may not be dereferenced anymore. Implemented here for the primitives and arrays of them. A struct of your own
+qualifies if it is '#[repr(C)]' (or transparent), its fields leave no gaps and none of them points anywhere. */
+
+/// A `Copy` type without padding or any other uninitialized bytes.
+///
+/// # Safety
+/// Every byte of every value of the type is initialized. For a struct that means `#[repr(C)]` or `#[repr(transparent)]`, fields that
+/// are all `NoUninit`, and no padding between or after them.
+/// The type must not contain pointers, references or function pointers.
+///
+/// ```
+/// use mlc::primitives::AtomicCopyCell::AtomicCopyCell;
+/// use mlc::primitives::NoUninit::NoUninit;
+///
+/// #[derive(Clone, Copy)]
+/// #[repr(C)]
+/// struct Pair {
+///     low: u32,
+///     high: u32,
+/// }
+///
+/// // Two u32s, no gaps.
+/// unsafe impl NoUninit for Pair {}
+///
+/// let cell = AtomicCopyCell::new(Pair { low: 1, high: 2 });
+/// assert_eq!(cell.load().high, 2);
+/// ```
+///
+/// Types with padding are rejected:
+///
+/// ```compile_fail
+/// use mlc::primitives::AtomicCopyCell::AtomicCopyCell;
+///
+/// // Three padding bytes after the u8.
+/// let cell = AtomicCopyCell::new((1u8, 2u32));
+/// ```
+///
+/// ```compile_fail
+/// use mlc::primitives::AtomicSeqCell::AtomicSeqCell;
+///
+/// #[derive(Clone, Copy)]
+/// struct Telemetry {
+///     samples: [u64; 8],
+///     flags: u8,
+/// }
+///
+/// // Not NoUninit: seven padding bytes after 'flags'.
+/// let cell = AtomicSeqCell::new(Telemetry { samples: [0; 8], flags: 0 });
+/// ```
+pub unsafe trait NoUninit: Copy + 'static {}
+
+macro_rules! no_uninit {
+    ($($t:ty),*) => {
+        $(unsafe impl NoUninit for $t {})*
+    };
+}
+
+no_uninit!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64, bool, char, ());
+
+unsafe impl<T: NoUninit, const N: usize> NoUninit for [T; N] {}
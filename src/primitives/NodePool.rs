@@ -0,0 +1,253 @@
+use crate::primitives::ACNode::ACNode;
+use crate::allocator::Allocator;
+use alloc::alloc::{handle_alloc_error, Layout};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::cell::UnsafeCell;
+use core::hint::spin_loop;
+use core::ops::{Deref, DerefMut};
+use core::ptr::{self, NonNull};
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/* How a node's memory gets back to its 'NodeHeap'. An ACNode only knows the address of the heap, not the allocator's type. */
+pub(crate) type Release<V> = unsafe fn(*mut ACNode<V>);
+
+/* Where a cell gets its ACNodes from. Plain Boxes, unless the cell was handed an allocator ('new_in', ...) or a pool ('with_node_pool'):
+only then does it box a 'NodeHeap' for its ACNodes to point to. */
+pub(crate) enum NodeSource<V, A: Allocator> {
+    /* Only built by the constructors without '_in', so A is 'Global'. */
+    Boxed(A),
+    Heap(Box<NodeHeap<V, A>>),
+}
+
+impl<V, A: Allocator> NodeSource<V, A> {
+    pub(crate) fn node(&self, value: V) -> *mut ACNode<V> {
+        match self {
+            Self::Boxed(_) => ACNode::new(value),
+            Self::Heap(heap) => ACNode::new_in(value, heap),
+        }
+    }
+
+    pub(crate) fn alloc(&self) -> &A {
+        match self {
+            Self::Boxed(alloc) => alloc,
+            Self::Heap(heap) => &heap.alloc,
+        }
+    }
+
+    pub(crate) fn pool(&self) -> Option<&NodePool<V>> {
+        match self {
+            Self::Boxed(_) => None,
+            Self::Heap(heap) => heap.pool.as_ref(),
+        }
+    }
+
+    /* Boxes a heap if there is none yet. The ACNodes made so far stay plain Boxes and are freed as such, A is 'Global' for them anyway. */
+    pub(crate) fn heap_mut(&mut self) -> &mut NodeHeap<V, A> {
+        if let Self::Boxed(alloc) = self {
+            unsafe {
+                // The allocator moves into the heap, the old 'Boxed' is overwritten without being dropped.
+                let alloc = ptr::read(alloc);
+                ptr::write(self, Self::Heap(Box::new(NodeHeap::new(alloc))));
+            }
+        }
+        match self {
+            Self::Heap(heap) => heap,
+            Self::Boxed(_) => unreachable!(),
+        }
+    }
+}
+
+/* The allocator of an 'AtomicCell', with an optional pool in front of it. The cell boxes it, so its ACNodes can point to it
+(see 'ACNode::new_in') no matter where the cell moves. */
+#[repr(C)]
+pub(crate) struct NodeHeap<V, A: Allocator> {
+    /* First, so the pointer an ACNode keeps to its heap is a pointer to this too. */
+    release: Release<V>,
+    pub(crate) alloc: A,
+    pub(crate) pool: Option<NodePool<V>>,
+}
+
+impl<V, A: Allocator> NodeHeap<V, A> {
+    pub(crate) fn new(alloc: A) -> Self {
+        Self { release: Self::release, alloc, pool: None }
+    }
+
+    /* Memory for one ACNode, from the pool if it has some. Its contents are garbage. */
+    pub(crate) fn allocate(&self) -> *mut ACNode<V> {
+        if let Some(node) = self.pool.as_ref().and_then(NodePool::take) {
+            return node;
+        }
+
+        let layout = Layout::new::<ACNode<V>>();
+        match self.alloc.allocate(layout) {
+            Ok(memory) => memory.cast::<ACNode<V>>().as_ptr(),
+            Err(_) => handle_alloc_error(layout),
+        }
+    }
+
+    /* What 'ACNode::free' ends up calling for nodes of this heap. The value is gone already, only the memory is left.
+    Safety: 'node' came from 'allocate' of the heap it points to, and that heap is still alive. */
+    unsafe fn release(node: *mut ACNode<V>) {
+        let heap = &*(*node).heap.cast::<Self>();
+        let node = match &heap.pool {
+            Some(pool) => match pool.put(node) {
+                Ok(()) => return,
+                Err(node) => node,
+            },
+            None => node,
+        };
+        dealloc_in(&heap.alloc, node);
+    }
+}
+
+impl<V, A: Allocator> Drop for NodeHeap<V, A> {
+    fn drop(&mut self) {
+        if let Some(pool) = &mut self.pool {
+            for node in pool.drain() {
+                // The values are long gone, only the memory is left.
+                unsafe { dealloc_in(&self.alloc, node) };
+            }
+        }
+    }
+}
+
+unsafe fn dealloc_in<V, A: Allocator>(alloc: &A, node: *mut ACNode<V>) {
+    alloc.deallocate(NonNull::new_unchecked(node).cast::<u8>(), Layout::new::<ACNode<V>>());
+}
+
+/* Keeps the allocations of freed ACNodes around so the next store can reuse one instead of going to the allocator, see
+'AtomicCell::with_node_pool'. Only the memory is pooled, the value in it is dropped as usual when the node is freed.
+The pool never blocks: if another thread is using it at the same moment, the allocator is used instead. */
+pub struct NodePool<V> {
+    cap: usize,
+    free: FreeList<V>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+/* A snapshot of what a 'NodePool' did so far. */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    /* New ACNodes that reused a pooled allocation. */
+    pub hits: usize,
+    /* New ACNodes that had to be allocated. */
+    pub misses: usize,
+    /* Allocations waiting in the pool right now. */
+    pub pooled: usize,
+}
+
+impl PoolStats {
+    /* Share of new ACNodes that came from the pool, 0.0 if there were none yet. */
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            return 0.0;
+        }
+        self.hits as f64 / total as f64
+    }
+}
+
+impl<V> NodePool<V> {
+    /* Holds on to at most 'cap' allocations. */
+    pub(crate) fn new(cap: usize) -> Self {
+        Self {
+            cap,
+            free: FreeList::new(cap),
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        }
+    }
+
+    /* An allocation for a new ACNode, None means go to the allocator. Its contents are garbage. */
+    pub(crate) fn take(&self) -> Option<*mut ACNode<V>> {
+        let node = self.free.try_lock().and_then(|mut free| free.pop());
+        match node {
+            Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.misses.fetch_add(1, Ordering::Relaxed),
+        };
+        node
+    }
+
+    /* Keeps the allocation of a node whose value is already dropped. Hands it back if the pool is full (or busy). */
+    pub(crate) fn put(&self, node: *mut ACNode<V>) -> Result<(), *mut ACNode<V>> {
+        if let Some(mut free) = self.free.try_lock() {
+            if free.len() < self.cap {
+                free.push(node);
+                return Ok(());
+            }
+        }
+        Err(node)
+    }
+
+    /* Everything in the pool, to be deallocated by its 'NodeHeap'. */
+    pub(crate) fn drain(&mut self) -> Vec<*mut ACNode<V>> {
+        core::mem::take(self.free.nodes.get_mut())
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        PoolStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            pooled: self.free.lock().len(),
+        }
+    }
+}
+
+/* The pooled allocations behind a flag instead of a Mutex, so the pool works without std. Stores and loads only ever try
+to take it, 'stats' is the only one that waits. */
+struct FreeList<V> {
+    locked: AtomicBool,
+    nodes: UnsafeCell<Vec<*mut ACNode<V>>>,
+}
+
+/* Holding one means nobody else touches the list. Dropping it lets go. */
+struct FreeListGuard<'a, V> {
+    list: &'a FreeList<V>,
+}
+
+impl<V> FreeList<V> {
+    fn new(cap: usize) -> Self {
+        Self {
+            locked: AtomicBool::new(false),
+            nodes: UnsafeCell::new(Vec::with_capacity(cap)),
+        }
+    }
+
+    fn try_lock(&self) -> Option<FreeListGuard<'_, V>> {
+        self.locked
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .ok()
+            .map(|_| FreeListGuard { list: self })
+    }
+
+    fn lock(&self) -> FreeListGuard<'_, V> {
+        loop {
+            if let Some(guard) = self.try_lock() {
+                return guard;
+            }
+            spin_loop();
+        }
+    }
+}
+
+impl<V> Deref for FreeListGuard<'_, V> {
+    type Target = Vec<*mut ACNode<V>>;
+
+    fn deref(&self) -> &Self::Target {
+        // Only the holder of the flag gets here.
+        unsafe { &*self.list.nodes.get() }
+    }
+}
+
+impl<V> DerefMut for FreeListGuard<'_, V> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *self.list.nodes.get() }
+    }
+}
+
+impl<V> Drop for FreeListGuard<'_, V> {
+    fn drop(&mut self) {
+        self.list.locked.store(false, Ordering::Release);
+    }
+}
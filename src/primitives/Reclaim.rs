@@ -0,0 +1,641 @@
+use crate::primitives::ACNode::ACNode;
+use crate::platform;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::ptr;
+use core::sync::atomic::{fence, AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+
+/* Reclaim decides when the ACNodes an 'AtomicCell' has replaced can be freed. The cell itself only ever does three things with a node:
+
+    protect   -> read the 'AtomicPtr' and make sure the node it points to stays alive while it is being read.
+    unprotect -> the read is over.
+    retire    -> the node was replaced by a newer one. Free it once nobody can be reading it anymore.
+
+The counter scheme ('CounterReclaim') is what 'AtomicCell' has always done. It is cheap, but as long as any read is in progress nothing
+can be freed, so a cell under constant read load never frees anything. 'EpochReclaim' frees in bounded time under the same load. */
+
+/// How an `AtomicCell` frees the nodes it replaced: `CounterReclaim`, `EpochReclaim` or `HazardReclaim`.
+///
+/// Sealed: only the strategies of this crate implement it. Its methods deal in raw ACNodes, which never leave the crate.
+///
+/// ```compile_fail
+/// use mlc::primitives::Reclaim::Reclaim;
+///
+/// struct Mine;
+///
+/// impl Reclaim for Mine {}
+/// ```
+pub trait Reclaim: sealed::Strategy + Send + Sync {}
+
+impl<S: sealed::Strategy + Send + Sync> Reclaim for S {}
+
+pub(crate) mod sealed {
+    use crate::primitives::ACNode::ACNode;
+    use core::sync::atomic::AtomicPtr;
+
+    /// # Safety
+    /// The cell dereferences every pointer returned by `protect` until the matching `unprotect`. An implementation that frees such a
+    /// node early causes a use-after-free.
+    pub unsafe trait Strategy {
+        /// Whatever an implementation needs to remember between `protect` and `unprotect`.
+        type Token;
+
+        /// Reads `ptr` and keeps the node it points to alive until `unprotect` is called with the returned token.
+        fn protect<T>(&self, ptr: &AtomicPtr<ACNode<T>>) -> (*mut ACNode<T>, Self::Token);
+
+        /// Ends a read started by `protect`.
+        ///
+        /// # Safety
+        /// `node` has to be the pointer returned together with `token`.
+        unsafe fn unprotect<T>(&self, node: *mut ACNode<T>, token: Self::Token);
+
+        /// `new` has replaced `old` in the cell. `old` is not reachable through the cell anymore and must be freed eventually.
+        ///
+        /// # Safety
+        /// Every node is retired exactly once, by the thread that unlinked it, and always with the same `T`.
+        unsafe fn retire<T>(&self, new: *mut ACNode<T>, old: *mut ACNode<T>);
+
+        /// Frees everything that was retired. `latest` is the current node, which stays alive, and the cell stays usable.
+        ///
+        /// # Safety
+        /// Only called with exclusive access to the cell (`Drop`, `get_mut`, ...), so nothing can be protected anymore.
+        unsafe fn drain<T>(&mut self, latest: *mut ACNode<T>);
+
+        /// How many retired nodes have not been freed yet.
+        fn pending(&self) -> usize;
+
+        /// Frees whatever can be freed right now without waiting for anyone. Returns how many nodes were freed.
+        ///
+        /// # Safety
+        /// `ptr` is the cell's pointer and `T` is the same as for `retire`.
+        unsafe fn try_reclaim<T>(&self, ptr: &AtomicPtr<ACNode<T>>) -> usize;
+
+        /// Like `try_reclaim`, but waits until the nodes retired before the call can be freed. Returns how many nodes were freed.
+        ///
+        /// # Safety
+        /// Same as `try_reclaim`.
+        unsafe fn reclaim_blocking<T>(&self, ptr: &AtomicPtr<ACNode<T>>) -> usize;
+    }
+}
+
+/* The default. Counts how many loads are currently in progress and only frees when it is alone. Retired nodes are kept in a chain
+hanging off the latest ACNode, see 'free'. */
+#[derive(Default)]
+pub struct CounterReclaim {
+    /* How many loads are currently in progress. After a load operation is finished it can decrement this value again.
+    Every write loads too, it has to protect the node it is about to replace. */
+    load_counter: AtomicUsize,
+    /* How many ACNodes are chained behind the latest one, waiting to be freed. */
+    retired_count: AtomicUsize,
+}
+
+impl CounterReclaim {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /* This function performs heavy logic to free memory. It is best understood after reading the implementation of ACNode.
+    It is marked as unsafe since it uses a raw pointer argument and requires that no threads hold pointers to the given ACNodes predecessors!
+    -> Guaranteed by load_counter.
+    Returns how many ACNodes were freed.
+    Not public! */
+    // The match spells out what happens when the cas fails, even if that is nothing.
+    #[allow(clippy::single_match)]
+    unsafe fn free<T>(&self, latest: *mut ACNode<T>) -> usize {
+        let mut freed = 0;
+
+        /* Remember the "chained flag" of ACNode? It signals whether an ACNode is fully initialized. To perform any operation we "unchain" the ACNode
+        thereby guranteering that is was chained and that no other thread can operate on it. */
+
+        // @MIRI: SEEMS LIKE MULTIPLE THREADS CAN ARRIVE HERE, CAUSING A DATA RACE
+        // FOUND ERROR: CHECKING FOR LOAD COUNTER == 0, then stall: => THREAD 1 has a pointer to a node (ptr1) with a free(ptr1) pending;
+        // THREAD 2 COMES IN AND READS THE LOAD COUNTER == 0, wanting to dealloc too => during dealloc it removes *ptr1 => race
+        match (*latest).chained_flag.compare_exchange(
+            true,
+            false,
+            Ordering::AcqRel,
+            // TODO Should be correct
+            Ordering::Relaxed,
+        ) {
+            /* If the cas on the first ACNode succeeds we can proceed...
+            Remember: "latest" is the pointer to the very first ("latest") ACNode. */
+            Ok(_) => {
+                /* Think of the following code as walking down the nodes of a linked list. There are 3 pointers involved:
+                - latest -> the very first pointer (head).
+                - prev_next_ptr -> the pointer with which we arrived at the ACNode we are currently at.
+                - next_next_ptr -> the pointer from the ACNode are at to another ACNode. This pointer will later replace prev_next_pointer and so on... */
+                let mut next_next_ptr: *mut ACNode<T> = *(*latest).next.get();
+
+                /* Checks if the latest ACNode is self-referential. Self-reference marks some "end" in the list.*/
+                if next_next_ptr != latest
+                // First node is not self-ref.
+                {
+                    /* Now we go one ACNode deep
+
+                                         |
+                                         | (latest)
+                                         |
+                        ---------    ----------
+                        -       <-----        -
+                        --------- |  ----------
+                              prev_next_ptr ( old next_next_ptr)
+                    */
+                    let mut prev_next_ptr = next_next_ptr;
+
+                    /* Now entering a loop. Note that this loop is finite. (We always make progress, no extra iterations can be created.) */
+                    loop {
+                        // prev_next_ptr is the read ptr from the previous iteration;
+                        match (*prev_next_ptr).chained_flag.compare_exchange(
+                            true,
+                            false,
+                            Ordering::AcqRel,
+                            // TODO Should be correct could leak mem otherwise
+                            Ordering::Relaxed,
+//                            Ordering::AcqRel,
+//                            Ordering::Acquire,
+                        ) {
+                            // Read the next ptr of the node
+                            // Note: We never deref next_next_ptr! Only as prev_next_ptr in the following iteration!
+                            Ok(_) => {
+                                next_next_ptr = *(*prev_next_ptr).next.get();
+
+                                if next_next_ptr == prev_next_ptr {
+                                    // This node is self-referential. Drop it! As it was the last node, we are done.
+                                    ACNode::free(prev_next_ptr);
+                                    freed += 1;
+                                                     // Make the first node self-ref, to mark as end.
+                                    // let dst = &mut (*latest).next as *mut *mut ACNode<T>;
+                                    let dst = (*latest).next.get();
+                                    let write_this = latest;
+                                    // write_volatile(dst, write_this);
+                                    *(dst) = write_this;
+                                    (*latest).chained_flag.store(true, Ordering::Release);
+                                    break;
+                                } else {
+                                    // This node has a next. Drop this node and proceed with its next ptr.
+                                    ACNode::free(prev_next_ptr);
+                                    freed += 1;
+                                    prev_next_ptr = next_next_ptr;
+                                }
+                            }
+                            Err(_) => {
+                                // This node is not chained, we cant drop it and we cant proceed. Therefore we "bridge" to it for future frees. Then we are done.
+                                *(*latest).next.get() = prev_next_ptr;
+
+//                                fence(Ordering::AcqRel);
+                                (*latest).chained_flag.store(true, Ordering::Release);
+
+//                                (*latest).chained_flag.store(true, Ordering::Release);
+                                break;
+                            }
+                        }
+                        /*
+                        Follow the previous next ptr.
+                        Check if the new ACNode is chained. If not, end.
+                        Check if the new ACNode is self-referential. If so, then its final, dealloc it and then end.
+                        Otherwise, if its neiter the final nor (the first, checked outside of loop nor) not init, dealloc it. And repeat again using its next-ptr
+                        in the next iteration.
+                        */
+                    }
+                } else {
+                    // Is self-ref, hit undo.
+                    (*latest).chained_flag.store(true, Ordering::Release);
+//                    (*latest).chained_flag.store(true, Ordering::Release);
+                }
+            }
+
+            /* If the cas failed than some other thread is working on it, freeing the memory for us. Great! We are done. */
+            Err(_) => (),
+        }
+
+        self.retired_count.fetch_sub(freed, Ordering::Release);
+        freed
+    }
+}
+
+unsafe impl sealed::Strategy for CounterReclaim {
+    type Token = ();
+
+    fn protect<T>(&self, ptr: &AtomicPtr<ACNode<T>>) -> (*mut ACNode<T>, ()) {
+        /* Marks that we perform a load operation. Since a thread may be stuck between getting the ptr and derefing it no frees must happen while a load is in progress.
+        Otherwise the ACNode may be removed from under our feet. */
+        self.load_counter.fetch_add(1, Ordering::AcqRel);
+
+        // Load visibly happens after the fetch_add
+        (ptr.load(Ordering::Acquire), ())
+    }
+
+    unsafe fn unprotect<T>(&self, node: *mut ACNode<T>, _token: ()) {
+        /* Again, free memory if possible. And mark the load operation as completed. */
+        // fetch_sub happens visibly after the load
+
+        // Only access is us:
+        if self.load_counter.load(Ordering::Acquire) == 1 {
+            self.free(node);
+        }
+        // TODO Release?
+        self.load_counter.fetch_sub(1, Ordering::Release);
+    }
+
+    unsafe fn retire<T>(&self, new: *mut ACNode<T>, old: *mut ACNode<T>) {
+        self.retired_count.fetch_add(1, Ordering::AcqRel);
+
+        /* This links the new ACNode to the old ACNode. Afterwards the new ACNode is considered "chained" because it points to it predecessor. */
+        // This is safeguarded by the chained flag. The write becomes visible to other threads after a sync with the fence.
+        *(*new).next.get() = old;
+        // Drop the safeguard of .next TODO Relax?
+        (*new).chained_flag.store(true, Ordering::Release);
+
+        /* Lastly it checks if freeing of memory can be done. */
+        // Communicate attempt at entering critical section
+        if self.load_counter.fetch_add(1, Ordering::AcqRel) == 0 {
+            self.free(new);
+        }
+        // TODO Release?
+        self.load_counter.fetch_sub(1, Ordering::Release);
+    }
+
+    unsafe fn drain<T>(&mut self, latest: *mut ACNode<T>) {
+        // No reference to the cell exists, so the load counter is 0 and every node is chained.
+        self.free(latest);
+    }
+
+    fn pending(&self) -> usize {
+        self.retired_count.load(Ordering::Acquire)
+    }
+
+    unsafe fn try_reclaim<T>(&self, ptr: &AtomicPtr<ACNode<T>>) -> usize {
+        // Same as the end of a store: only free if nobody else is in.
+        let mut freed = 0;
+        if self.load_counter.fetch_add(1, Ordering::AcqRel) == 0 {
+            freed = self.free(ptr.load(Ordering::Acquire));
+        }
+        self.load_counter.fetch_sub(1, Ordering::Release);
+        freed
+    }
+
+    unsafe fn reclaim_blocking<T>(&self, ptr: &AtomicPtr<ACNode<T>>) -> usize {
+        /* Wait for the load counter to drain. Note that a cell that is read constantly may never get there. */
+        while self
+            .load_counter
+            .compare_exchange_weak(0, 1, Ordering::AcqRel, Ordering::Relaxed)
+            .is_err()
+        {
+            platform::yield_now();
+        }
+        let freed = self.free(ptr.load(Ordering::Acquire));
+        self.load_counter.fetch_sub(1, Ordering::Release);
+        freed
+    }
+}
+
+/* A stack of retired ACNodes, linked through their 'next' pointer, for the schemes that keep them on the side instead of chained to the
+cell. Type-erased, the cell only ever hands us one type of node. */
+#[derive(Default)]
+struct Retired {
+    head: AtomicPtr<()>,
+}
+
+impl Retired {
+    /* Pushes the list 'head' .. 'tail' (already linked). */
+    unsafe fn push<T>(&self, head: *mut ACNode<T>, tail: *mut ACNode<T>) {
+        let mut current = self.head.load(Ordering::Relaxed);
+        loop {
+            *(*tail).next.get() = current as *mut ACNode<T>;
+            match self.head.compare_exchange_weak(
+                current,
+                head as *mut (),
+                Ordering::Release,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return,
+                Err(actual) => current = actual,
+            }
+        }
+    }
+
+    /* Takes the whole list, leaving this one empty. */
+    fn take(&self) -> Retired {
+        Retired {
+            head: AtomicPtr::new(self.head.swap(ptr::null_mut(), Ordering::Acquire)),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.head.load(Ordering::Acquire).is_null()
+    }
+
+    /* Takes the whole list, frees every node 'keep' turns down and puts the rest back. Several threads may sift at the same time, each
+    one only ever sees the nodes it took. Returns how many nodes were freed. */
+    unsafe fn sift<T>(&self, mut keep: impl FnMut(*mut ACNode<T>) -> bool) -> usize {
+        let mut node = self.head.swap(ptr::null_mut(), Ordering::Acquire) as *mut ACNode<T>;
+        let mut keep_head: *mut ACNode<T> = ptr::null_mut();
+        let mut keep_tail: *mut ACNode<T> = ptr::null_mut();
+        let mut freed = 0;
+
+        while !node.is_null() {
+            let next = *(*node).next.get();
+            if keep(node) {
+                *(*node).next.get() = keep_head;
+                if keep_head.is_null() {
+                    keep_tail = node;
+                }
+                keep_head = node;
+            } else {
+                ACNode::free(node);
+                freed += 1;
+            }
+            node = next;
+        }
+
+        if !keep_head.is_null() {
+            self.push(keep_head, keep_tail);
+        }
+        freed
+    }
+
+    /* Frees everything. Nobody else can touch the list anymore. */
+    unsafe fn drain<T>(&mut self) {
+        let mut node = *self.head.get_mut() as *mut ACNode<T>;
+        while !node.is_null() {
+            let next = *(*node).next.get();
+            ACNode::free(node);
+            node = next;
+        }
+        *self.head.get_mut() = ptr::null_mut();
+    }
+}
+
+/* Epoch based reclamation. Every read "pins" the epoch it started in, and every retired node is stamped with the epoch it was retired in.
+The epoch can only move on from E to E + 1 once no read pinned in E - 1 is left, so by the time the epoch is E all reads that are still
+in progress started in E - 1 or later. A node retired in E - 2 or earlier can't be seen by any of them anymore and is freed.
+
+Reads in the current epoch never hold up the reclamation of older nodes, so a cell that is read constantly still frees its nodes.
+Only a single read that never ends can block it. */
+#[derive(Default)]
+pub struct EpochReclaim {
+    epoch: AtomicUsize,
+    /* Reads currently pinned, indexed by the parity of the epoch they are pinned in. Only two epochs can have pinned reads at a time. */
+    pinned: [AtomicUsize; 2],
+    garbage: Retired,
+    garbage_count: AtomicUsize,
+}
+
+impl EpochReclaim {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /* Moves the epoch on if no read is pinned in the one before the current. Returns the (possibly new) epoch. */
+    fn try_advance(&self) -> usize {
+        let epoch = self.epoch.load(Ordering::SeqCst);
+        // The epoch before the current one has the same parity as the one after it.
+        if self.pinned[(epoch + 1) % 2].load(Ordering::SeqCst) == 0 {
+            let _ = self
+                .epoch
+                .compare_exchange(epoch, epoch + 1, Ordering::SeqCst, Ordering::SeqCst);
+        }
+        self.epoch.load(Ordering::SeqCst)
+    }
+
+    /* Frees what is old enough from the garbage list. Returns how many ACNodes were freed. */
+    unsafe fn collect<T>(&self) -> usize {
+        let epoch = self.try_advance();
+        let freed = self.garbage.sift::<T>(|node| *(*node).retired_epoch.get() + 2 > epoch);
+        self.garbage_count.fetch_sub(freed, Ordering::Release);
+        freed
+    }
+}
+
+unsafe impl sealed::Strategy for EpochReclaim {
+    /* Index into 'pinned'. */
+    type Token = usize;
+
+    fn protect<T>(&self, ptr: &AtomicPtr<ACNode<T>>) -> (*mut ACNode<T>, usize) {
+        let slot = loop {
+            let epoch = self.epoch.load(Ordering::SeqCst);
+            let slot = epoch % 2;
+            self.pinned[slot].fetch_add(1, Ordering::SeqCst);
+            // If the epoch moved on in the meantime our pin might not have been seen. Try again.
+            if self.epoch.load(Ordering::SeqCst) == epoch {
+                break slot;
+            }
+            self.pinned[slot].fetch_sub(1, Ordering::SeqCst);
+        };
+        // Pairs with the fence in retire: either we see the newer node, or the retirer sees our epoch.
+        fence(Ordering::SeqCst);
+
+        (ptr.load(Ordering::Acquire), slot)
+    }
+
+    unsafe fn unprotect<T>(&self, _node: *mut ACNode<T>, token: usize) {
+        self.pinned[token].fetch_sub(1, Ordering::SeqCst);
+    }
+
+    unsafe fn retire<T>(&self, _new: *mut ACNode<T>, old: *mut ACNode<T>) {
+        fence(Ordering::SeqCst);
+        *(*old).retired_epoch.get() = self.epoch.load(Ordering::SeqCst);
+        self.garbage_count.fetch_add(1, Ordering::AcqRel);
+        self.garbage.push(old, old);
+
+        self.collect::<T>();
+    }
+
+    unsafe fn drain<T>(&mut self, _latest: *mut ACNode<T>) {
+        self.garbage.drain::<T>();
+        *self.garbage_count.get_mut() = 0;
+    }
+
+    fn pending(&self) -> usize {
+        self.garbage_count.load(Ordering::Acquire)
+    }
+
+    unsafe fn try_reclaim<T>(&self, _ptr: &AtomicPtr<ACNode<T>>) -> usize {
+        self.collect::<T>()
+    }
+
+    unsafe fn reclaim_blocking<T>(&self, _ptr: &AtomicPtr<ACNode<T>>) -> usize {
+        /* Everything retired so far is stamped with the current epoch or an older one. Two epochs later all of it can go.
+        Each step has to wait for the reads pinned in the epoch before. */
+        let target = self.epoch.load(Ordering::SeqCst) + 2;
+        while self.try_advance() < target {
+            platform::yield_now();
+        }
+        self.collect::<T>()
+    }
+}
+
+/* Once this many nodes are retired, retiring another one scans the hazards and frees everything not protected. */
+pub const HAZARD_SCAN_THRESHOLD: usize = 64;
+
+/* Hazard pointer based reclamation. A read publishes the one node it is about to dereference in a hazard record, and only that node is
+kept alive. Retired nodes are freed by scanning the records once enough of them have piled up.
+
+Unlike the other schemes, this bounds the number of retired but not yet freed nodes regardless of how the cell is read:
+    HAZARD_SCAN_THRESHOLD + hazard records + 2 * threads retiring at the same time
+Every protection in progress needs a record of its own. Records are reused, so there are never more than the most reads ever in
+progress at the same time. */
+#[derive(Default)]
+pub struct HazardReclaim {
+    /* Append-only list of hazard records, freed together with the reclaimer. */
+    hazards: AtomicPtr<HazardRecord>,
+    retired: Retired,
+    retired_count: AtomicUsize,
+}
+
+struct HazardRecord {
+    protected: AtomicPtr<()>,
+    active: AtomicBool,
+    next: *mut HazardRecord,
+}
+
+/* The record a read is using. Handed back on 'unprotect'. */
+pub struct HazardToken {
+    record: *const HazardRecord,
+}
+
+impl HazardReclaim {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /* How many hazard records exist, i.e. the most protections that were ever in progress at the same time. */
+    pub fn hazards(&self) -> usize {
+        let mut count = 0;
+        let mut record = self.hazards.load(Ordering::Acquire);
+        while !record.is_null() {
+            count += 1;
+            record = unsafe { (*record).next };
+        }
+        count
+    }
+
+    /* Finds an unused record or adds a new one. */
+    fn acquire(&self) -> &HazardRecord {
+        let mut record = self.hazards.load(Ordering::Acquire);
+        while !record.is_null() {
+            let candidate = unsafe { &*record };
+            if !candidate.active.load(Ordering::Relaxed)
+                && candidate
+                    .active
+                    .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+                    .is_ok()
+            {
+                return candidate;
+            }
+            record = candidate.next;
+        }
+
+        let new = Box::into_raw(Box::new(HazardRecord {
+            protected: AtomicPtr::new(ptr::null_mut()),
+            active: AtomicBool::new(true),
+            next: ptr::null_mut(),
+        }));
+        let mut head = self.hazards.load(Ordering::Relaxed);
+        loop {
+            unsafe { (*new).next = head };
+            match self
+                .hazards
+                .compare_exchange_weak(head, new, Ordering::AcqRel, Ordering::Relaxed)
+            {
+                Ok(_) => return unsafe { &*new },
+                Err(actual) => head = actual,
+            }
+        }
+    }
+
+    /* Frees every node on 'list' that is not protected by a hazard. Returns how many ACNodes were freed. */
+    unsafe fn scan<T>(&self, list: &Retired) -> usize {
+        // Pairs with the fence in protect: either the reader sees the node was replaced, or we see its hazard.
+        fence(Ordering::SeqCst);
+
+        let mut protected = Vec::new();
+        let mut record = self.hazards.load(Ordering::Acquire);
+        while !record.is_null() {
+            let hazard = (*record).protected.load(Ordering::SeqCst);
+            if !hazard.is_null() {
+                protected.push(hazard);
+            }
+            record = (*record).next;
+        }
+
+        let freed = list.sift::<T>(|node| protected.contains(&(node as *mut ())));
+        self.retired_count.fetch_sub(freed, Ordering::Release);
+        freed
+    }
+}
+
+unsafe impl sealed::Strategy for HazardReclaim {
+    type Token = HazardToken;
+
+    fn protect<T>(&self, ptr: &AtomicPtr<ACNode<T>>) -> (*mut ACNode<T>, HazardToken) {
+        let record = self.acquire();
+
+        let mut node = ptr.load(Ordering::Acquire);
+        loop {
+            record.protected.store(node as *mut (), Ordering::SeqCst);
+            // Pairs with the fence in scan.
+            fence(Ordering::SeqCst);
+            // Still the latest? Then it was not retired before our hazard became visible.
+            let again = ptr.load(Ordering::Acquire);
+            if again == node {
+                break;
+            }
+            node = again;
+        }
+
+        (node, HazardToken { record })
+    }
+
+    unsafe fn unprotect<T>(&self, _node: *mut ACNode<T>, token: HazardToken) {
+        let record = &*token.record;
+        record.protected.store(ptr::null_mut(), Ordering::Release);
+        record.active.store(false, Ordering::Release);
+    }
+
+    unsafe fn retire<T>(&self, _new: *mut ACNode<T>, old: *mut ACNode<T>) {
+        /* Counted before it is pushed: once on the list a concurrent scan may free it and subtract it again, and the count must not
+        drop below zero in between. */
+        let pending = self.retired_count.fetch_add(1, Ordering::AcqRel) + 1;
+        self.retired.push(old, old);
+        if pending >= HAZARD_SCAN_THRESHOLD {
+            self.scan::<T>(&self.retired);
+        }
+    }
+
+    unsafe fn drain<T>(&mut self, _latest: *mut ACNode<T>) {
+        self.retired.drain::<T>();
+        *self.retired_count.get_mut() = 0;
+    }
+
+    fn pending(&self) -> usize {
+        self.retired_count.load(Ordering::Acquire)
+    }
+
+    unsafe fn try_reclaim<T>(&self, _ptr: &AtomicPtr<ACNode<T>>) -> usize {
+        self.scan::<T>(&self.retired)
+    }
+
+    unsafe fn reclaim_blocking<T>(&self, _ptr: &AtomicPtr<ACNode<T>>) -> usize {
+        /* Takes the nodes retired so far off the shared list, so whatever is retired while we wait isn't waited for. Nobody can protect
+        a node that was already replaced, so they can only be held up by reads that are in progress right now. Wait for those to let go.
+        (Nodes a concurrent scan has taken at this moment are left to it.) */
+        let mine = self.retired.take();
+        let mut freed = self.scan::<T>(&mine);
+        while !mine.is_empty() {
+            platform::yield_now();
+            freed += self.scan::<T>(&mine);
+        }
+        freed
+    }
+}
+
+impl Drop for HazardReclaim {
+    fn drop(&mut self) {
+        let mut record = *self.hazards.get_mut();
+        while !record.is_null() {
+            let boxed = unsafe { Box::from_raw(record) };
+            record = boxed.next;
+        }
+    }
+}
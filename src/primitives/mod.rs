@@ -1 +1,12 @@
-pub mod AtomicCell;
\ No newline at end of file
+// Module files are named after the type they hold.
+#![allow(non_snake_case)]
+
+mod ACNode;
+pub mod AtomicCell;
+pub mod AtomicCopyCell;
+pub mod AtomicOptionCell;
+pub mod AtomicSeqCell;
+pub mod Backoff;
+pub mod NoUninit;
+pub mod NodePool;
+pub mod Reclaim;
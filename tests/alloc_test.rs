@@ -0,0 +1,114 @@
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
+
+#[path = "common/threads.rs"]
+mod threads;
+#[path = "common/reclaim.rs"]
+#[macro_use]
+mod reclaim;
+
+use mlc::allocator::{AllocError, Allocator, Global};
+use mlc::collections::MlcVec::AtomicVec;
+use mlc::primitives::AtomicCell::*;
+use std::alloc::Layout;
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use threads::sum_threads;
+
+/* Counts what goes through it, everything else is Global's business. */
+#[derive(Clone, Default)]
+struct Counting {
+    allocs: Arc<AtomicUsize>,
+    deallocs: Arc<AtomicUsize>,
+}
+
+unsafe impl Allocator for Counting {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.allocs.fetch_add(1, Ordering::SeqCst);
+        Global.allocate(layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        self.deallocs.fetch_add(1, Ordering::SeqCst);
+        Global.deallocate(ptr, layout)
+    }
+}
+
+impl Counting {
+    fn allocs(&self) -> usize {
+        self.allocs.load(Ordering::SeqCst)
+    }
+
+    fn deallocs(&self) -> usize {
+        self.deallocs.load(Ordering::SeqCst)
+    }
+}
+
+#[test]
+fn cell_nodes_use_the_allocator() {
+    let alloc = Counting::default();
+    let cell = AtomicCell::new_in(0u64, alloc.clone());
+    assert_eq!(alloc.allocs(), 1);
+
+    for n in 1..=10 {
+        cell.store(n);
+    }
+    let _ = cell.fetch_update(|x| (Arc::new(*x + 1), ()));
+    assert_eq!(*cell.load(), 11);
+    assert_eq!(alloc.allocs(), 12);
+    assert_eq!(cell.allocator().allocs(), 12);
+
+    drop(cell);
+    assert_eq!(alloc.deallocs(), 12);
+}
+
+#[test]
+fn pool_in_front_of_the_allocator() {
+    let alloc = Counting::default();
+    let cell = AtomicCell::from_arc_in(Arc::new(0u64), alloc.clone()).with_node_pool(4);
+
+    for n in 1..=100 {
+        cell.store(n);
+        cell.try_reclaim();
+    }
+    // After warming up the pool, stores stop hitting the allocator.
+    assert!(alloc.allocs() < 10, "{} allocations", alloc.allocs());
+
+    drop(cell);
+    assert_eq!(alloc.allocs(), alloc.deallocs());
+}
+
+#[test]
+fn summing_with_every_reclaim() {
+    for_every_reclaim!(|reclaim| {
+        let alloc = Counting::default();
+        let fancy_cell = AtomicCell::with_reclaim_in(0u64, reclaim, alloc.clone());
+
+        sum_threads(8, |_| {
+            for _ in 0..200 {
+                fancy_cell.fetch_add(1).unwrap();
+            }
+            0
+        });
+
+        assert_eq!(*fancy_cell.load(), 8 * 200);
+        drop(fancy_cell);
+        // One ACNode per update plus the first, and one more for every cas that was lost. Every one of them is given back.
+        assert!(alloc.allocs() > 8 * 200, "{} allocations", alloc.allocs());
+        assert_eq!(alloc.deallocs(), alloc.allocs());
+    });
+}
+
+#[test]
+fn vec_in_allocator() {
+    let alloc = Counting::default();
+    let vec = AtomicVec::new_with_capacity_in(4, alloc.clone());
+    vec.push(1u64);
+    vec.push(2);
+    assert_eq!(vec.pop().map(|x| *x), Some(2));
+    assert_eq!(vec.get(0).map(|x| *x), Some(1));
+
+    drop(vec);
+    assert_eq!(alloc.allocs(), 4);
+    assert_eq!(alloc.deallocs(), 4);
+}
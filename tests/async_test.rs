@@ -0,0 +1,131 @@
+use mlc::primitives::AtomicCell::*;
+use std::future::Future;
+use std::pin::pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+use std::time::Duration;
+
+/* Just enough of an executor to run a future on the current thread: park until woken, poll again. Counts wakes so tests can check
+how often a future was woken. */
+struct ThreadWaker {
+    thread: Thread,
+    wakes: AtomicUsize,
+}
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.wakes.fetch_add(1, Ordering::SeqCst);
+        self.thread.unpark();
+    }
+}
+
+fn block_on<F: Future>(future: F) -> (F::Output, usize) {
+    let mut future = pin!(future);
+    let thread_waker = Arc::new(ThreadWaker {
+        thread: thread::current(),
+        wakes: AtomicUsize::new(0),
+    });
+    let waker = Waker::from(thread_waker.clone());
+    let mut cx = Context::from_waker(&waker);
+
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return (output, thread_waker.wakes.load(Ordering::SeqCst));
+        }
+        thread::park();
+    }
+}
+
+#[test]
+fn changed_ready_right_away() {
+    let cell = AtomicCell::new(0u64);
+    let seen = cell.load();
+    cell.store(1);
+
+    let (current, wakes) = block_on(cell.changed(&seen));
+    assert_eq!(*current, 1);
+    assert_eq!(wakes, 0);
+}
+
+#[test]
+fn changed_wakes_on_publish() {
+    let fancy_cell = Arc::new(AtomicCell::new(0u64));
+    let seen = fancy_cell.load();
+
+    let cell = fancy_cell.clone();
+    let publisher = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(20));
+        // Same Arc again, not a change.
+        let same = cell.load();
+        cell.store_arc(same);
+        thread::sleep(Duration::from_millis(20));
+        cell.store(1);
+    });
+
+    let (current, wakes) = block_on(fancy_cell.changed(&seen));
+    assert_eq!(*current, 1);
+    assert!(wakes >= 1);
+    publisher.join().unwrap();
+}
+
+#[test]
+fn changed_many_tasks() {
+    let fancy_cell = Arc::new(AtomicCell::new(0u64));
+
+    let tasks = (0..8)
+        .map(|_| {
+            let cell = fancy_cell.clone();
+            thread::spawn(move || {
+                let mut seen = cell.load();
+                while *seen < 100 {
+                    seen = block_on(cell.changed(&seen)).0;
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for i in 1..=100 {
+        let _ = fancy_cell.fetch_update(|_| (Arc::new(i), ()));
+    }
+    for t in tasks {
+        t.join().unwrap();
+    }
+}
+
+#[test]
+fn dropped_changed_lets_go_of_its_waker() {
+    let cell = AtomicCell::new(0u64);
+    let seen = cell.load();
+    let thread_waker = Arc::new(ThreadWaker {
+        thread: thread::current(),
+        wakes: AtomicUsize::new(0),
+    });
+    let waker = Waker::from(thread_waker.clone());
+    let mut cx = Context::from_waker(&waker);
+
+    for _ in 0..100 {
+        let mut changed = pin!(cell.changed(&seen));
+        assert!(changed.as_mut().poll(&mut cx).is_pending());
+        assert!(changed.as_mut().poll(&mut cx).is_pending());
+    }
+    // Only 'waker' itself and ours are left, none of the 100 dropped futures kept a clone.
+    assert_eq!(Arc::strong_count(&thread_waker), 2);
+
+    cell.store(1);
+    assert_eq!(thread_waker.wakes.load(Ordering::SeqCst), 0);
+
+    // A live one is still woken.
+    let seen = cell.load();
+    let mut later = pin!(cell.changed(&seen));
+    assert!(later.as_mut().poll(&mut cx).is_pending());
+    assert_eq!(Arc::strong_count(&thread_waker), 3);
+    cell.store(2);
+    assert_eq!(thread_waker.wakes.load(Ordering::SeqCst), 1);
+    assert_eq!(Arc::strong_count(&thread_waker), 2);
+}
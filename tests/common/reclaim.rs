@@ -0,0 +1,25 @@
+/* Shared by the integration tests, include it with '#[path = "common/reclaim.rs"] #[macro_use] mod reclaim;'. */
+
+/* Runs the same test body once for every 'Reclaim', with 'reclaim' bound to a fresh one:
+
+    for_every_reclaim!(|reclaim| {
+        let cell = AtomicCell::with_reclaim(0u64, reclaim);
+        ...
+    });
+*/
+macro_rules! for_every_reclaim {
+    (|$reclaim:ident| $body:block) => {{
+        {
+            let $reclaim = mlc::primitives::Reclaim::CounterReclaim::new();
+            $body
+        }
+        {
+            let $reclaim = mlc::primitives::Reclaim::EpochReclaim::new();
+            $body
+        }
+        {
+            let $reclaim = mlc::primitives::Reclaim::HazardReclaim::new();
+            $body
+        }
+    }};
+}
@@ -0,0 +1,23 @@
+/* Shared by the integration tests, include it with '#[path = "common/threads.rs"] mod threads;'. */
+
+use std::sync::Barrier;
+use std::thread;
+
+/* Runs 'op' on 'threads' threads at once and adds up what they return, e.g. how many updates got through. 'op' gets the index of its
+thread. All of them wait for each other before they start, so they actually contend. A panic in any of them fails the test. */
+pub fn sum_threads(threads: usize, op: impl Fn(usize) -> u64 + Sync) -> u64 {
+    let bar = Barrier::new(threads);
+
+    thread::scope(|s| {
+        let handles = (0..threads)
+            .map(|i| {
+                let (bar, op) = (&bar, &op);
+                s.spawn(move || {
+                    bar.wait();
+                    op(i)
+                })
+            })
+            .collect::<Vec<_>>();
+        handles.into_iter().map(|h| h.join().unwrap()).sum()
+    })
+}
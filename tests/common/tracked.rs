@@ -0,0 +1,32 @@
+/* Shared by the integration tests, include it with '#[path = "common/tracked.rs"] mod tracked;'. */
+
+use std::ops::Deref;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/* A value that counts itself in 'live' for as long as it exists, to see which values a cell has actually freed. Every test brings its own
+static counter, so tests running at the same time don't count each other's values. */
+pub struct Tracked<T = ()> {
+    live: &'static AtomicUsize,
+    value: T,
+}
+
+impl<T> Tracked<T> {
+    pub fn new(live: &'static AtomicUsize, value: T) -> Self {
+        live.fetch_add(1, Ordering::SeqCst);
+        Tracked { live, value }
+    }
+}
+
+impl<T> Deref for Tracked<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> Drop for Tracked<T> {
+    fn drop(&mut self) {
+        self.live.fetch_sub(1, Ordering::SeqCst);
+    }
+}
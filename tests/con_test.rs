@@ -1,11 +1,23 @@
+// Also built as the 'contestant' bin, where the tests (and the imports only they use) are compiled out.
+#![cfg_attr(not(test), allow(unused_imports))]
+
+use mlc::error::CasFailed;
 use mlc::primitives::AtomicCell::*;
 use std::{sync::Arc, thread};
 
+#[cfg(test)]
+#[path = "common/threads.rs"]
+mod threads;
+#[cfg(test)]
+use threads::sum_threads;
+
 fn main(){
     println!("Hello from con_test main!");
 }
 
 
+// The *_bash helpers aren't called anywhere right now, they are kept for ad hoc runs.
+#[allow(dead_code)]
 fn store_bash<T>(cell: Arc<AtomicCell<T>>, new: T)
 where
     T: Clone,
@@ -15,12 +27,14 @@ where
     }
 }
 
+#[allow(dead_code)]
 fn load_bash<T>(cell: Arc<AtomicCell<T>>) {
     for _ in 0..10 {
         let _ = cell.load();
     }
 }
 
+#[allow(dead_code)]
 fn swap_bash<T>(cell: Arc<AtomicCell<T>>, new: T)
 where
     T: Clone,
@@ -38,6 +52,7 @@ fn first(){
     }
 
 #[test]
+#[allow(unused_must_use)]
 fn acell_store() {
     let fancy_cell = Arc::new(AtomicCell::new("Bonjour"));
 
@@ -63,3 +78,286 @@ fn summing() {
 
     assert_eq!((*fancy_cell.load()), 100)
 }
+
+#[test]
+fn acell_arc_variants() {
+    let shared = Arc::new(String::from("Bonjour"));
+    let cell = AtomicCell::from_arc(shared.clone());
+    assert!(Arc::ptr_eq(&cell.load(), &shared));
+
+    let other = Arc::new(String::from("Salut"));
+    cell.store_arc(other.clone());
+    assert!(Arc::ptr_eq(&cell.load(), &other));
+
+    let old = cell.swap_arc(shared.clone());
+    assert!(Arc::ptr_eq(&old, &other));
+    assert!(Arc::ptr_eq(&cell.load(), &shared));
+}
+
+#[test]
+fn acell_compare_exchange() {
+    let first = Arc::new(1u64);
+    let cell = AtomicCell::from_arc(first.clone());
+
+    // Equal value, different Arc: rejected.
+    let CasFailed { current: actual, rejected } = cell.compare_exchange(&Arc::new(1), Arc::new(2)).unwrap_err();
+    assert_eq!(*rejected, 2);
+    assert!(Arc::ptr_eq(&actual, &first));
+
+    let previous = cell.compare_exchange(&first, Arc::new(3)).unwrap();
+    assert!(Arc::ptr_eq(&previous, &first));
+    assert_eq!(*cell.load(), 3);
+}
+
+#[test]
+fn acell_compare_exchange_summing() {
+    let cell = AtomicCell::new(0u64);
+
+    let total = sum_threads(10, |_| {
+        for _ in 0..100 {
+            let mut current = cell.load();
+            let mut new = Arc::new(*current + 1);
+            while let Err(CasFailed { current: actual, rejected }) = cell.compare_exchange(&current, new) {
+                current = actual;
+                new = rejected;
+                *Arc::get_mut(&mut new).unwrap() = *current + 1;
+            }
+        }
+        100
+    });
+    assert_eq!(*cell.load(), total);
+}
+
+#[test]
+fn acell_load_guard() {
+    let cell = AtomicCell::new(String::from("Bonjour"));
+    let first = cell.load();
+
+    {
+        let guard = cell.load_guard();
+        cell.store(String::from("Salut"));
+        // The guard still sees the value it was created with, without holding a reference to the Arc.
+        assert_eq!(*guard, "Bonjour");
+        assert_eq!(Arc::strong_count(&first), 2);
+    }
+
+    assert_eq!(cell.with(|s| s.len()), 5);
+    assert_eq!(*cell.load_guard(), "Salut");
+}
+
+#[test]
+fn acell_load_guard_concurrent() {
+    let fancy_cell = Arc::new(AtomicCell::new(vec![0u64; 16]));
+
+    let handles = (0..8u64)
+        .map(|i| {
+            let cell = fancy_cell.clone();
+            thread::spawn(move || {
+                for _ in 0..100 {
+                    if i % 2 == 0 {
+                        cell.store(vec![i; 16]);
+                    } else {
+                        let guard = cell.load_guard();
+                        assert!(guard.iter().all(|x| *x == guard[0]));
+                    }
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for h in handles {
+        h.join().unwrap();
+    }
+}
+
+#[test]
+fn acell_unsized() {
+    let text: AtomicCell<str> = AtomicCell::from_arc(Arc::from("Bonjour"));
+    assert_eq!(&*text.swap_arc(Arc::from("Salut")), "Bonjour");
+    assert_eq!(text.with(str::len), 5);
+    let _ = text.fetch_update(|s| (Arc::from(s.to_uppercase()), ()));
+    assert_eq!(&*text.load(), "SALUT");
+
+    let numbers: AtomicCell<[u64]> = AtomicCell::from_arc(Arc::new([1, 2, 3]));
+    numbers.store_arc(Arc::new([4, 5]));
+    assert_eq!(&*numbers.load_guard(), &[4, 5]);
+
+    trait Handler {
+        fn handle(&self, x: u64) -> u64;
+    }
+    struct Double;
+    impl Handler for Double {
+        fn handle(&self, x: u64) -> u64 {
+            x * 2
+        }
+    }
+    struct Square;
+    impl Handler for Square {
+        fn handle(&self, x: u64) -> u64 {
+            x * x
+        }
+    }
+
+    let handler: Arc<AtomicCell<dyn Handler + Send + Sync>> = Arc::new(AtomicCell::from_arc(Arc::new(Double)));
+    assert_eq!(handler.load().handle(3), 6);
+    let cell = handler.clone();
+    thread::spawn(move || cell.store_arc(Arc::new(Square))).join().unwrap();
+    assert_eq!(handler.load().handle(3), 9);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn acell_wait_changed() {
+    let fancy_cell = Arc::new(AtomicCell::new(0u64));
+    let seen = fancy_cell.load();
+
+    let timeout = std::time::Duration::from_millis(10);
+    assert!(fancy_cell.wait_changed_timeout(&seen, timeout).is_none());
+    // Re-storing the very same Arc is not a change.
+    fancy_cell.store_arc(seen.clone());
+    assert!(fancy_cell.wait_changed_timeout(&seen, timeout).is_none());
+
+    let waiters = (0..4)
+        .map(|_| {
+            let cell = fancy_cell.clone();
+            let seen = seen.clone();
+            thread::spawn(move || *cell.wait_changed(&seen))
+        })
+        .collect::<Vec<_>>();
+
+    thread::sleep(timeout);
+    let _ = fancy_cell.fetch_update(|x| (Arc::new(*x + 1), ()));
+
+    for w in waiters {
+        assert_eq!(w.join().unwrap(), 1);
+    }
+    // Already changed, no waiting.
+    assert_eq!(*fancy_cell.wait_changed(&seen), 1);
+}
+
+#[test]
+fn acell_versions() {
+    let cell = AtomicCell::new(0u64);
+    let (value, version) = cell.load_versioned();
+    assert_eq!((*value, version), (0, 0));
+
+    // Re-storing the very same Arc is still a new version.
+    cell.store_arc(value.clone());
+    assert_eq!(cell.version(), 1);
+    let _ = cell.swap(5);
+    let _ = cell.fetch_update(|x| (Arc::new(*x + 1), ()));
+    assert_eq!(cell.load_versioned().1, 3);
+
+    let CasFailed { current: actual, rejected } = cell.compare_exchange_version(1, Arc::new(7)).unwrap_err();
+    assert_eq!((*rejected, actual), (7, 3));
+    let previous = cell.compare_exchange_version(3, Arc::new(7)).unwrap();
+    assert_eq!(*previous, 6);
+    assert_eq!(cell.load_versioned().1, 4);
+}
+
+#[test]
+fn acell_versions_concurrent() {
+    let fancy_cell = Arc::new(AtomicCell::new(0u64));
+
+    let handles = (0..10u64)
+        .map(|i| {
+            let cell = fancy_cell.clone();
+            thread::spawn(move || {
+                let mut last = 0;
+                for _ in 0..100 {
+                    match i % 3 {
+                        0 => cell.store(i),
+                        1 => loop {
+                            let (value, version) = cell.load_versioned();
+                            if cell.compare_exchange_version(version, Arc::new(*value)).is_ok() {
+                                break;
+                            }
+                        },
+                        _ => {
+                            // Versions never go backwards.
+                            let version = cell.version();
+                            assert!(version >= last);
+                            last = version;
+                        }
+                    }
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for h in handles {
+        h.join().unwrap();
+    }
+
+    // Every store and every successful compare_exchange_version bumped the version exactly once.
+    assert_eq!(fancy_cell.version(), 4 * 100 + 3 * 100);
+}
+
+#[test]
+fn acell_try_update() {
+    let cell = AtomicCell::new(7u64);
+
+    // Same shape as AtomicU64::fetch_update.
+    assert_eq!(cell.try_update(|_| None).unwrap(), Err(Arc::new(7)));
+    assert_eq!(cell.try_update(|x| Some(x + 1)).unwrap(), Ok(Arc::new(7)));
+    assert_eq!(cell.update_and_fetch(|x| Some(x * 2)).unwrap(), Ok(Arc::new(16)));
+    assert_eq!(cell.update_and_fetch(|x| (*x < 10).then_some(0)).unwrap(), Err(Arc::new(16)));
+    assert_eq!(*cell.load(), 16);
+
+    // A panic propagates and leaves the cell usable.
+    let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| cell.try_update(|_| panic!("nope"))));
+    assert!(panicked.is_err());
+    cell.store(1);
+    assert_eq!(*cell.load(), 1);
+}
+
+#[test]
+fn acell_try_update_summing() {
+    let cell = AtomicCell::new(0u64);
+
+    let total = sum_threads(10, |_| {
+        for _ in 0..100 {
+            cell.try_update(|x| Some(x + 1)).unwrap().unwrap();
+        }
+        100
+    });
+
+    assert_eq!(*cell.load(), total);
+}
+
+#[test]
+fn acell_arithmetic() {
+    let cell = AtomicCell::new(10u64);
+    assert_eq!(*cell.fetch_add(5).unwrap(), 10);
+    assert_eq!(*cell.fetch_sub(3).unwrap(), 15);
+    assert_eq!(*cell.fetch_max(4).unwrap(), 12);
+    assert_eq!(*cell.fetch_max(20).unwrap(), 12);
+    assert_eq!(*cell.fetch_min(30).unwrap(), 20);
+    assert_eq!(*cell.fetch_min(6).unwrap(), 20);
+    assert_eq!(*cell.fetch_or(0b1001).unwrap(), 6);
+    assert_eq!(*cell.fetch_and(0b1100).unwrap(), 0b1111);
+    assert_eq!(*cell.fetch_xor(0b0110).unwrap(), 0b1100);
+    assert_eq!(*cell.load(), 0b1010);
+    // fetch_max and fetch_min that don't change anything don't store.
+    assert_eq!(cell.version(), 7);
+
+    // Anything with the operator goes.
+    let text = AtomicCell::new(String::from("Bon"));
+    assert_eq!(&**text.fetch_max(String::from("A")).unwrap(), "Bon");
+    let floats = AtomicCell::new(1.5f64);
+    floats.fetch_add(0.25).unwrap();
+    assert_eq!(*floats.load(), 1.75);
+}
+
+#[test]
+fn acell_fetch_add_summing() {
+    let fancy_cell = AtomicCell::new(0u64);
+
+    sum_threads(10, |_| {
+        for _ in 0..100 {
+            fancy_cell.fetch_add(2).unwrap();
+            fancy_cell.fetch_sub(1).unwrap();
+        }
+        0
+    });
+
+    assert_eq!(*fancy_cell.load(), 1000);
+}
@@ -0,0 +1,91 @@
+use mlc::primitives::AtomicCopyCell::AtomicCopyCell;
+use std::sync::Arc;
+use std::thread;
+
+#[path = "common/threads.rs"]
+mod threads;
+use threads::sum_threads;
+
+#[test]
+fn copy_cell_native() {
+    let cell = AtomicCopyCell::new(5u32);
+    assert_eq!(cell.load(), 5);
+    assert_eq!(cell.swap(6), 5);
+    cell.store(7);
+    assert_eq!(cell.fetch_update(|x| (x * 2, x)).unwrap(), 7);
+    assert_eq!(cell.load(), 14);
+
+    let floats = AtomicCopyCell::new(-0.5f64);
+    assert_eq!(floats.swap(2.5), -0.5);
+    assert_eq!(floats.load(), 2.5);
+
+    let small = AtomicCopyCell::new([1u8, 2, 3]);
+    small.store([4, 5, 6]);
+    assert_eq!(small.load(), [4, 5, 6]);
+
+    let unit = AtomicCopyCell::new(());
+    unit.store(());
+    assert!(AtomicCopyCell::<u64>::default().load() == 0);
+}
+
+#[test]
+fn copy_cell_seqlock() {
+    let cell = AtomicCopyCell::new([1u64, 2]);
+    assert_eq!(cell.swap([3, 4]), [1, 2]);
+    let _ = cell.fetch_update(|[a, b]| ([b, a], ()));
+    assert_eq!(cell.load(), [4, 3]);
+
+    let odd = AtomicCopyCell::new([7u8; 11]);
+    odd.store([9; 11]);
+    assert_eq!(odd.load(), [9; 11]);
+
+    // A panicking closure leaves the cell as it was.
+    #[cfg(feature = "std")]
+    {
+        // Same shape as the fetch_updates of 'AtomicCell'.
+        let panicked = cell.fetch_update(|_| -> ([u64; 2], ()) { panic!("nope") });
+        assert!(matches!(panicked, Err(mlc::error::UpdateError::Panicked(_))));
+        assert_eq!(cell.load(), [4, 3]);
+    }
+}
+
+#[test]
+fn copy_cell_no_torn_reads() {
+    let fancy_cell = Arc::new(AtomicCopyCell::new([0u64; 2]));
+
+    let handles = (0..8u64)
+        .map(|i| {
+            let cell = fancy_cell.clone();
+            thread::spawn(move || {
+                for n in 0..10_000u64 {
+                    if i % 2 == 0 {
+                        cell.store([i * n; 2]);
+                    } else {
+                        let [a, b] = cell.load();
+                        assert_eq!(a, b);
+                    }
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for h in handles {
+        h.join().unwrap();
+    }
+}
+
+#[test]
+fn copy_cell_summing() {
+    let words = AtomicCopyCell::new(0u64);
+    let pairs = AtomicCopyCell::new([0u64, 0]);
+
+    sum_threads(10, |_| {
+        for _ in 0..1000 {
+            words.fetch_update(|x| (x + 1, ())).unwrap();
+            pairs.fetch_update(|[a, b]| ([a + 1, b + 2], ())).unwrap();
+        }
+        0
+    });
+
+    assert_eq!(words.load(), 10_000);
+    assert_eq!(pairs.load(), [10_000, 20_000]);
+}
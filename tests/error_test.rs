@@ -0,0 +1,79 @@
+use mlc::collections::MlcVec::AtomicVec;
+use mlc::error::*;
+use mlc::primitives::AtomicCell::*;
+use mlc::primitives::Backoff::Backoff;
+use std::error::Error;
+use std::sync::Arc;
+
+#[test]
+fn cas_by_eq_hands_back_both() {
+    let cell = AtomicCell::new(1u64);
+    assert_eq!(cell.cas_by_eq(&1, 2).unwrap(), Ok(()));
+
+    let failed = cell.cas_by_eq(&1, 3).unwrap().unwrap_err();
+    assert_eq!((*failed.current, failed.rejected), (2, 3));
+    assert_eq!(*cell.load(), 2);
+    assert_eq!(cell.version(), 1);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn closure_panicked() {
+    let cell = AtomicCell::new(1u64);
+
+    let Err(UpdateError::Panicked(panicked)) = cell.fetch_update(|_| -> (Arc<u64>, ()) { panic!("nope {}", 7) }) else {
+        panic!("expected the panic back")
+    };
+    assert_eq!(panicked.message(), Some("nope 7"));
+    assert_eq!(panicked.to_string(), "the update closure panicked: nope 7");
+}
+
+#[test]
+fn vec_remove() {
+    let vec = AtomicVec::new();
+    for i in 0..3u64 {
+        vec.push(i);
+    }
+
+    assert_eq!(*vec.remove(1).unwrap(), 1);
+    assert_eq!(vec.remove(2).unwrap_err(), IndexOutOfBounds { index: 2, len: 2 });
+    assert_eq!(vec.get_beam().iter().map(|x| **x).collect::<Vec<_>>(), [0, 2]);
+}
+
+// Everything goes through '?' the same way.
+fn bump_twice(cell: &AtomicCell<u64>) -> Result<u64, Box<dyn Error>> {
+    let seen = cell.load();
+    cell.compare_exchange(&seen, Arc::new(*seen + 1))?;
+    let previous = cell.try_fetch_update(4, Backoff::Spin, |x| (Arc::new(*x + 1), *x))?;
+    Ok(previous)
+}
+
+#[test]
+fn errors_compose() {
+    let cell = AtomicCell::new(0u64);
+    assert_eq!(bump_twice(&cell).unwrap(), 1);
+
+    let err: Box<dyn Error> = cell.cas_by_eq(&0, 9).unwrap().unwrap_err().into();
+    assert!(err.downcast_ref::<CasFailed<u64, Arc<u64>>>().is_some());
+}
+
+// All of them are Send + Sync, so they fit the error type threads hand around too.
+fn bump_from_anywhere(cell: &AtomicCell<u64>) -> Result<u64, Box<dyn Error + Send + Sync>> {
+    Ok(cell.fetch_update(|x| (Arc::new(*x + 1), *x))?)
+}
+
+#[test]
+fn errors_are_send_sync() {
+    let cell = Arc::new(AtomicCell::new(0u64));
+    let handle = std::thread::spawn({
+        let cell = cell.clone();
+        move || bump_from_anywhere(&cell)
+    });
+    assert_eq!(handle.join().unwrap().unwrap(), 0);
+
+    #[cfg(feature = "std")]
+    {
+        let err: Box<dyn Error + Send + Sync> = cell.fetch_update(|_| -> (Arc<u64>, ()) { panic!("nope") }).unwrap_err().into();
+        assert_eq!(err.to_string(), "the update closure panicked: nope");
+    }
+}
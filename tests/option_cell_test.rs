@@ -0,0 +1,53 @@
+use mlc::primitives::AtomicOptionCell::*;
+use std::{sync::Arc, thread};
+
+#[test]
+fn option_cell() {
+    let cell = AtomicOptionCell::new(None);
+    assert!(cell.is_none());
+    assert!(cell.take().is_none());
+
+    assert!(cell.store_if_empty(String::from("Bonjour")).is_ok());
+    assert_eq!(cell.store_if_empty(String::from("Salut")), Err(String::from("Salut")));
+    assert_eq!(*cell.load().unwrap(), "Bonjour");
+
+    assert_eq!(*cell.swap(String::from("Salut")).unwrap(), "Bonjour");
+    assert_eq!(*cell.take().unwrap(), "Salut");
+    assert!(cell.is_none());
+    assert!(cell.load().is_none());
+}
+
+#[test]
+fn option_cell_take_once() {
+    let fancy_cell = Arc::new(AtomicOptionCell::new(Some(0u64)));
+
+    // Every value stored is taken by exactly one thread.
+    let handles = (0..10u64)
+        .map(|i| {
+            let cell = fancy_cell.clone();
+            thread::spawn(move || {
+                let mut taken = 0;
+                for _ in 0..100 {
+                    while cell.store_if_empty(i).is_err() {
+                        if cell.take().is_some() {
+                            taken += 1;
+                        }
+                    }
+                }
+                taken
+            })
+        })
+        .collect::<Vec<_>>();
+    let taken: u64 = handles.into_iter().map(|h| h.join().unwrap()).sum();
+    let left = fancy_cell.take().map_or(0, |_| 1);
+
+    assert_eq!(taken + left, 1001);
+}
+
+#[test]
+fn option_cell_unsized() {
+    let cell: AtomicOptionCell<str> = AtomicOptionCell::from_arc(None);
+    cell.store_arc(Arc::from("Bonjour"));
+    assert_eq!(cell.take().as_deref(), Some("Bonjour"));
+    assert!(cell.is_none());
+}
@@ -0,0 +1,55 @@
+#[path = "common/reclaim.rs"]
+#[macro_use]
+mod reclaim;
+
+use mlc::primitives::AtomicCell::*;
+use mlc::primitives::Reclaim::*;
+use std::sync::Arc;
+
+#[test]
+fn get_mut_unique() {
+    for_every_reclaim!(|reclaim| {
+        let mut cell = AtomicCell::with_reclaim(vec![1u64], reclaim);
+        cell.get_mut().unwrap().push(2);
+        assert_eq!(*cell.load(), [1, 2]);
+
+        // Storing the same Arc again leaves a clone in the replaced ACNode, which is freed first.
+        let shared = cell.load();
+        cell.store_arc(shared.clone());
+        cell.store_arc(shared.clone());
+        assert!(cell.get_mut().is_none());
+        drop(shared);
+        cell.get_mut().unwrap().push(3);
+        assert_eq!(*cell.load(), [1, 2, 3]);
+        assert_eq!(cell.pending_nodes(), 0);
+
+        // Still a working cell afterwards.
+        cell.store(vec![4]);
+        assert_eq!(*cell.load(), [4]);
+    });
+}
+
+#[test]
+fn into_inner_and_owned() {
+    let cell = AtomicCell::new(String::from("a"));
+    for s in ["b", "c", "d"] {
+        cell.store(s.to_owned());
+    }
+    assert_eq!(cell.try_into_owned().unwrap(), "d");
+
+    let cell = AtomicCell::with_reclaim(String::from("a"), HazardReclaim::new());
+    let kept = cell.load();
+    assert!(Arc::ptr_eq(&cell.try_into_owned().unwrap_err(), &kept));
+
+    let cell: AtomicCell<str> = AtomicCell::from_arc(Arc::from("unsized"));
+    cell.store_arc(Arc::from("still unsized"));
+    assert_eq!(&*cell.into_inner(), "still unsized");
+}
+
+#[test]
+fn take_leaves_default() {
+    let cell = AtomicCell::new(vec![1u64, 2, 3]);
+    assert_eq!(*cell.take(), [1, 2, 3]);
+    assert!(cell.load().is_empty());
+    assert_eq!(cell.version(), 1);
+}
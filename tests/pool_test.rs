@@ -0,0 +1,69 @@
+#[path = "common/threads.rs"]
+mod threads;
+#[path = "common/reclaim.rs"]
+#[macro_use]
+mod reclaim;
+#[path = "common/tracked.rs"]
+mod tracked;
+
+use mlc::primitives::AtomicCell::*;
+use threads::sum_threads;
+use tracked::Tracked;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+static POOL_LIVE: AtomicUsize = AtomicUsize::new(0);
+
+#[test]
+fn pool_reuses_nodes() {
+    let cell = AtomicCell::new(Tracked::new(&POOL_LIVE, 0u64)).with_node_pool(8);
+    assert_eq!(AtomicCell::new(0).pool_stats(), None);
+
+    for n in 1..=100 {
+        cell.store(Tracked::new(&POOL_LIVE, n));
+        // Nothing is being read, so the replaced node can go back right away.
+        cell.try_reclaim();
+    }
+
+    let stats = cell.pool_stats().unwrap();
+    assert_eq!(stats.hits + stats.misses, 100);
+    assert!(stats.hit_rate() > 0.9, "{stats:?}");
+    assert!(stats.pooled <= 8);
+    assert_eq!(**cell.load(), 100);
+
+    // Pooling keeps the memory, not the values.
+    assert_eq!(POOL_LIVE.load(Ordering::SeqCst), 1);
+    drop(cell);
+    assert_eq!(POOL_LIVE.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+#[should_panic(expected = "already has a node pool")]
+fn pool_only_once() {
+    let _ = AtomicCell::new(0).with_node_pool(4).with_node_pool(4);
+}
+
+#[test]
+fn pool_summing() {
+    for_every_reclaim!(|reclaim| {
+        let fancy_cell = AtomicCell::with_reclaim(0u64, reclaim).with_node_pool(16);
+
+        sum_threads(8, |i| {
+            for _ in 0..500 {
+                fancy_cell.fetch_update(|x| (Arc::new(*x + 1), ())).unwrap();
+                if i % 2 == 0 {
+                    // The node behind a guard must not be handed out again while the guard is alive.
+                    let guard = fancy_cell.load_guard();
+                    let seen = *guard;
+                    // Still a new node per call.
+                    fancy_cell.fetch_add(0).unwrap();
+                    assert_eq!(*guard, seen);
+                }
+            }
+            0
+        });
+
+        assert_eq!(*fancy_cell.load(), 8 * 500);
+        assert!(fancy_cell.pool_stats().unwrap().hits > 0);
+    });
+}
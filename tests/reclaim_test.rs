@@ -0,0 +1,216 @@
+#[path = "common/threads.rs"]
+mod threads;
+#[path = "common/reclaim.rs"]
+#[macro_use]
+mod reclaim;
+#[path = "common/tracked.rs"]
+mod tracked;
+
+use mlc::primitives::AtomicCell::*;
+use mlc::primitives::Reclaim::*;
+use threads::sum_threads;
+use tracked::Tracked;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::{sync::Arc, thread};
+
+#[test]
+fn epoch_summing() {
+    let fancy_cell = AtomicCell::with_reclaim(0u64, EpochReclaim::new());
+
+    let total = sum_threads(100, |_| {
+        fancy_cell.fetch_update::<(), _>(|cell| (Arc::new((*cell) + 1), ())).unwrap();
+        1
+    });
+
+    assert_eq!((*fancy_cell.load()), total)
+}
+
+// Counts values that have not been dropped yet, i.e. ACNodes that have not been freed.
+static EPOCH_LIVE: AtomicUsize = AtomicUsize::new(0);
+
+#[test]
+fn epoch_reclaims_under_read_load() {
+    let fancy_cell = Arc::new(AtomicCell::with_reclaim(Tracked::new(&EPOCH_LIVE, ()), EpochReclaim::new()));
+    let stop = Arc::new(AtomicBool::new(false));
+
+    let readers = (0..4)
+        .map(|_| {
+            let cell = fancy_cell.clone();
+            let stop = stop.clone();
+            thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    let _ = cell.load();
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for _ in 0..20_000 {
+        fancy_cell.store(Tracked::new(&EPOCH_LIVE, ()));
+    }
+    // The readers never stopped, yet most of the replaced values are gone already.
+    assert!(EPOCH_LIVE.load(Ordering::SeqCst) < 10_000);
+
+    stop.store(true, Ordering::Relaxed);
+    for r in readers {
+        r.join().unwrap();
+    }
+
+    drop(fancy_cell);
+    assert_eq!(EPOCH_LIVE.load(Ordering::SeqCst), 0);
+}
+
+static HAZARD_LIVE: AtomicUsize = AtomicUsize::new(0);
+
+#[test]
+fn hazard_bounds_retired_nodes() {
+    const WRITERS: usize = 4;
+    const READERS: usize = 8;
+
+    let fancy_cell = Arc::new(AtomicCell::with_reclaim(Tracked::new(&HAZARD_LIVE, 0u64), HazardReclaim::new()));
+    let stop = Arc::new(AtomicBool::new(false));
+
+    let readers = (0..READERS)
+        .map(|_| {
+            let cell = fancy_cell.clone();
+            let stop = stop.clone();
+            thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    // Hold on to the node for a while, guards keep it protected.
+                    let guard = cell.load_guard();
+                    let seen = **guard;
+                    thread::yield_now();
+                    assert_eq!(**guard, seen);
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let writers = (0..WRITERS)
+        .map(|w| {
+            let cell = fancy_cell.clone();
+            thread::spawn(move || {
+                for i in 0..5_000u64 {
+                    cell.store(Tracked::new(&HAZARD_LIVE, i * WRITERS as u64 + w as u64));
+                    let bound = HAZARD_SCAN_THRESHOLD + cell.reclaimer().hazards() + 2 * WRITERS;
+                    let pending = cell.pending_nodes();
+                    assert!(pending <= bound, "{} retired nodes, bound is {}", pending, bound);
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for w in writers {
+        w.join().unwrap();
+    }
+    stop.store(true, Ordering::Relaxed);
+    for r in readers {
+        r.join().unwrap();
+    }
+
+    // Everything that is still alive is accounted for by the retired list and the latest value.
+    assert_eq!(HAZARD_LIVE.load(Ordering::SeqCst), fancy_cell.pending_nodes() + 1);
+    drop(fancy_cell);
+    assert_eq!(HAZARD_LIVE.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn hazard_summing() {
+    let fancy_cell = AtomicCell::with_reclaim(0u64, HazardReclaim::new());
+
+    let total = sum_threads(100, |_| {
+        for _ in 0..100 {
+            fancy_cell.fetch_update::<(), _>(|cell| (Arc::new((*cell) + 1), ())).unwrap();
+        }
+        100
+    });
+
+    assert_eq!(*fancy_cell.load(), total);
+    assert!(fancy_cell.pending_nodes() <= HAZARD_SCAN_THRESHOLD + fancy_cell.reclaimer().hazards() + 200);
+}
+
+#[test]
+fn counter_introspection() {
+    let cell = AtomicCell::new(0u64);
+
+    let guard = cell.load_guard();
+    for i in 1..=10 {
+        cell.store(i);
+    }
+    // The guard's load is still in progress, nothing can be freed.
+    assert_eq!(cell.pending_nodes(), 10);
+    assert_eq!(cell.try_reclaim(), 0);
+    assert_eq!(*guard, 0);
+    drop(guard);
+
+    assert_eq!(cell.try_reclaim(), 10);
+    assert_eq!(cell.pending_nodes(), 0);
+}
+
+#[test]
+fn reclaim_blocking_waits_for_loads() {
+    for_every_reclaim!(|reclaim| {
+        let cell = AtomicCell::with_reclaim(0u64, reclaim);
+        let guard = cell.load_guard();
+        for i in 1..=10 {
+            cell.store(i);
+        }
+        assert!(cell.pending_nodes() > 0);
+
+        let released = AtomicBool::new(false);
+        thread::scope(|s| {
+            let reclaiming = s.spawn(|| {
+                cell.reclaim_blocking();
+                // Only gets here once the guard is gone.
+                assert!(released.load(Ordering::SeqCst));
+            });
+            thread::sleep(std::time::Duration::from_millis(10));
+            released.store(true, Ordering::SeqCst);
+            drop(guard);
+            reclaiming.join().unwrap();
+        });
+        assert_eq!(cell.pending_nodes(), 0);
+    });
+}
+
+type Cell = AtomicCell<u64>;
+
+#[test]
+fn counter_frees_replaced_right_away() {
+    let cell = AtomicCell::new(0u64);
+    let mut kept = cell.load();
+
+    // Every kind of write frees what it replaced before returning, as long as nobody else is reading.
+    let writes: [&dyn Fn(&Cell); 7] = [
+        &|cell| cell.store(1),
+        &|cell| drop(cell.swap(2)),
+        &|cell| drop(cell.compare_exchange(&cell.load(), Arc::new(3))),
+        &|cell| drop(cell.compare_exchange_version(cell.version(), Arc::new(4))),
+        &|cell| assert!(cell.cas_by_eq(&4, 5).unwrap().is_ok()),
+        &|cell| drop(cell.fetch_update(|x| (Arc::new(*x + 1), ()))),
+        &|cell| cell.rcu(|x| *x += 1).unwrap(),
+    ];
+    for write in writes {
+        write(&cell);
+        assert_eq!(cell.pending_nodes(), 0);
+        assert_eq!(Arc::strong_count(&kept), 1);
+        kept = cell.load();
+    }
+    assert_eq!(*kept, 7);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn panicking_update_releases_its_node() {
+    let cell = AtomicCell::new(0u64);
+
+    let panicked = cell.fetch_update(|_| -> (Arc<u64>, ()) { panic!("nope") });
+    assert!(panicked.is_err());
+
+    // Had the panic left the node protected, nothing could be freed anymore.
+    for i in 1..=10 {
+        cell.store(i);
+    }
+    cell.try_reclaim();
+    assert_eq!(cell.pending_nodes(), 0);
+}
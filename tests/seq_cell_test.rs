@@ -0,0 +1,103 @@
+use mlc::primitives::AtomicSeqCell::AtomicSeqCell;
+use mlc::primitives::NoUninit::NoUninit;
+use std::sync::{Arc, Barrier};
+use std::thread;
+
+#[path = "common/threads.rs"]
+mod threads;
+use threads::sum_threads;
+
+/* 256 bytes, every field always holds the same number. A torn read mixes two stores and shows up as differing fields. */
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[repr(C)]
+struct Telemetry {
+    samples: [u64; 30],
+    sum: u64,
+    tag: u64,
+}
+
+// All u64s, no padding.
+unsafe impl NoUninit for Telemetry {}
+
+impl Telemetry {
+    fn all(n: u64) -> Self {
+        Self {
+            samples: [n; 30],
+            sum: n,
+            tag: n,
+        }
+    }
+
+    fn is_whole(&self) -> bool {
+        self.samples.iter().all(|s| *s == self.sum) && self.sum == self.tag
+    }
+}
+
+#[test]
+fn seq_cell_basics() {
+    let cell = AtomicSeqCell::new(Telemetry::all(1));
+    assert_eq!(cell.load(), Telemetry::all(1));
+    assert_eq!(cell.swap(Telemetry::all(2)), Telemetry::all(1));
+    cell.store(Telemetry::all(3));
+    assert_eq!(cell.fetch_update(|t| (Telemetry::all(t.tag * 2), t.tag)).unwrap(), 3);
+    assert_eq!(cell.load(), Telemetry::all(6));
+
+    // Sizes that aren't a multiple of 8.
+    let odd = AtomicSeqCell::new([1u8; 67]);
+    odd.store([2; 67]);
+    assert_eq!(odd.load(), [2; 67]);
+    assert_eq!(AtomicSeqCell::<[u32; 3]>::default().load(), [0; 3]);
+
+    #[cfg(feature = "std")]
+    {
+        // Same shape as the fetch_updates of 'AtomicCell'.
+        let panicked = cell.fetch_update(|_| -> (Telemetry, ()) { panic!("nope") });
+        assert!(matches!(panicked, Err(mlc::error::UpdateError::Panicked(_))));
+        assert_eq!(cell.load(), Telemetry::all(6));
+    }
+}
+
+#[test]
+fn seq_cell_no_torn_reads() {
+    let fancy_cell = Arc::new(AtomicSeqCell::new(Telemetry::all(0)));
+    let bar = Arc::new(Barrier::new(8));
+
+    let handles = (0..8u64)
+        .map(|i| {
+            let cell = fancy_cell.clone();
+            let bar = bar.clone();
+            thread::spawn(move || {
+                bar.wait();
+                for n in 0..5_000u64 {
+                    match i % 4 {
+                        0 => cell.store(Telemetry::all(i * n)),
+                        1 => assert!(cell.swap(Telemetry::all(n)).is_whole()),
+                        _ => assert!(cell.load().is_whole()),
+                    }
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for h in handles {
+        h.join().unwrap();
+    }
+}
+
+#[test]
+fn seq_cell_summing() {
+    let fancy_cell = AtomicSeqCell::new(Telemetry::all(0));
+
+    sum_threads(10, |_| {
+        for _ in 0..1000 {
+            fancy_cell
+                .fetch_update(|t| {
+                    assert!(t.is_whole());
+                    (Telemetry::all(t.tag + 1), ())
+                })
+                .unwrap();
+        }
+        0
+    });
+
+    assert_eq!(fancy_cell.load(), Telemetry::all(10_000));
+}
@@ -0,0 +1,371 @@
+use mlc::error::*;
+use mlc::primitives::AtomicCell::*;
+use mlc::primitives::Backoff::Backoff;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Barrier};
+use std::thread;
+use std::time::Duration;
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+#[path = "common/threads.rs"]
+mod threads;
+use threads::sum_threads;
+
+#[test]
+fn bounded_fetch_update() {
+    let cell = AtomicCell::new(1u64);
+
+    let done = cell.try_fetch_update(3, Backoff::Spin, |x| (Arc::new(*x + 1), *x)).unwrap();
+    assert_eq!(done, 1);
+    assert_eq!(*cell.load(), 2);
+
+    let mut calls = 0;
+    let gave_up = cell.try_fetch_update(0, Backoff::Spin, |x| {
+        calls += 1;
+        (x, ())
+    });
+    assert!(matches!(gave_up, Err(UpdateError::RetriesExhausted(RetriesExhausted { attempts: 0 }))));
+    assert_eq!(calls, 0);
+
+    // A closure that always changes the cell behind our back never gets its cas through.
+    let mut calls = 0;
+    let gave_up = cell.try_fetch_update(5, Backoff::Yield, |x| {
+        calls += 1;
+        cell.store(*x + 1);
+        (x, ())
+    });
+    assert!(matches!(gave_up, Err(UpdateError::RetriesExhausted(RetriesExhausted { attempts: 5 }))));
+    assert_eq!(calls, 5);
+    assert_eq!(*cell.load(), 7);
+
+    // Panics are reported like 'fetch_update' does.
+    #[cfg(feature = "std")]
+    assert!(matches!(
+        cell.try_fetch_update(1, Backoff::Spin, |_| -> (Arc<u64>, ()) { panic!("nope") }),
+        Err(UpdateError::Panicked(_))
+    ));
+    cell.store(0);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn deadline_fetch_update() {
+    let cell = AtomicCell::new(0u64);
+    let backoff = Backoff::Exponential {
+        initial: Duration::from_millis(1),
+        max: Duration::from_millis(4),
+    };
+
+    // A deadline in the past still gets one attempt.
+    cell.fetch_update_until(Instant::now(), backoff, |x| (Arc::new(*x + 1), ())).unwrap();
+
+    let start = Instant::now();
+    let deadline = start + Duration::from_millis(30);
+    let gave_up = cell.fetch_update_until(deadline, backoff, |x| {
+        cell.store(*x + 1);
+        (x, ())
+    });
+    let Err(UpdateError::RetriesExhausted(gave_up)) = gave_up else {
+        panic!("expected to give up")
+    };
+    assert!(gave_up.attempts > 1);
+    assert!(Instant::now() >= deadline);
+    // Sleeps are cut short at the deadline, the bound only catches a backoff that ignores it.
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn bounded_fetch_update_summing() {
+    let fancy_cell = AtomicCell::new(0u64);
+
+    let done = sum_threads(10, |_| {
+        let mut done = 0;
+        for _ in 0..100 {
+            if fancy_cell.try_fetch_update(4, Backoff::Spin, |x| (Arc::new(*x + 1), ())).is_ok() {
+                done += 1;
+            }
+        }
+        done
+    });
+
+    // Whatever gave up stored nothing.
+    assert_eq!(*fancy_cell.load(), done);
+}
+
+#[test]
+fn slow_updater_is_not_starved() {
+    let fancy_cell = Arc::new(AtomicCell::new(0u64).fallback_after(4));
+    let done = Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let bar = Arc::new(Barrier::new(9));
+
+    let fast = (0..8)
+        .map(|_| {
+            let cell = fancy_cell.clone();
+            let done = done.clone();
+            let bar = bar.clone();
+            thread::spawn(move || {
+                bar.wait();
+                while !done.load(std::sync::atomic::Ordering::Relaxed) {
+                    let _ = cell.fetch_update(|x| (Arc::new(*x + 1), ()));
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    bar.wait();
+    let mut calls = 0;
+    let _ = fancy_cell.fetch_update(|x| {
+        calls += 1;
+        thread::sleep(Duration::from_millis(2));
+        (Arc::new(*x + 1), ())
+    });
+    done.store(true, std::sync::atomic::Ordering::Relaxed);
+    for h in fast {
+        h.join().unwrap();
+    }
+
+    // Lines up after 4 failures, then only the 8 cas already in flight can beat it.
+    assert!(calls <= 4 + 1 + 8, "{calls} attempts");
+}
+
+#[test]
+fn fallback_summing() {
+    // Lining up after the first failure pushes nearly everything through the fallback.
+    let fancy_cell = AtomicCell::new(0u64).fallback_after(1);
+
+    sum_threads(12, |i| {
+        for _ in 0..200 {
+            match i % 3 {
+                0 => {
+                    let _ = fancy_cell.fetch_update(|x| (Arc::new(*x + 1), ()));
+                }
+                1 => {
+                    fancy_cell.try_update(|x| Some(x + 1)).unwrap().unwrap();
+                }
+                _ => {
+                    let _ = fancy_cell.try_fetch_update(usize::MAX, Backoff::Yield, |x| (Arc::new(*x + 1), ()));
+                }
+            }
+        }
+        0
+    });
+
+    assert_eq!(*fancy_cell.load(), 12 * 200);
+    // Everyone is out of line again, plain stores still go through.
+    fancy_cell.store(0);
+    assert_eq!(fancy_cell.version(), 12 * 200 + 1);
+}
+
+#[test]
+fn fallback_is_opt_in() {
+    assert_eq!(DEFAULT_FALLBACK_AFTER, usize::MAX);
+
+    // An update that runs another update on its own cell loses its cas every time. Once in line it would wait for its own turn, without
+    // a fallback it just takes more attempts. Far more than anyone would pass to 'fallback_after'.
+    let losses = 100;
+    let cell = AtomicCell::new(0u64);
+    let mut calls = 0;
+    cell.try_update(|x| {
+        calls += 1;
+        if calls <= losses {
+            cell.try_update(|x| Some(x + 1)).unwrap().unwrap();
+        }
+        Some(x + 1)
+    })
+    .unwrap()
+    .unwrap();
+    assert_eq!(calls, losses + 1);
+    assert_eq!(*cell.load(), losses as u64 + 1);
+}
+
+#[test]
+fn fallback_never_holds_up_stores_or_bounded_updates() {
+    let cell = AtomicCell::new(0u64).fallback_after(1);
+    let in_line = Barrier::new(2);
+    let (others_done, wait_for_others) = mpsc::channel();
+
+    thread::scope(|s| {
+        // Loses once against the store below, then lines up and keeps its turn until everything else went through.
+        let (cell, in_line) = (&cell, &in_line);
+        let holder = s.spawn(move || {
+            let mut calls = 0;
+            let mut saw_others = false;
+            let _ = cell.fetch_update(|x| {
+                calls += 1;
+                if calls == 1 {
+                    cell.store(100);
+                } else if calls == 2 {
+                    in_line.wait();
+                    // Only times out if the others got stuck behind us.
+                    saw_others = wait_for_others.recv_timeout(Duration::from_secs(10)).is_ok();
+                }
+                (Arc::new(*x + 1), ())
+            });
+            saw_others
+        });
+        in_line.wait();
+
+        cell.store(7);
+        let _ = cell.swap(8);
+        assert!(cell.try_fetch_update(1, Backoff::Spin, |x| (x, ())).is_ok());
+        #[cfg(feature = "std")]
+        {
+            let deadline = Instant::now() + Duration::from_millis(10);
+            assert!(cell.fetch_update_until(deadline, Backoff::Spin, |x| (x, ())).is_ok());
+        }
+        others_done.send(()).unwrap();
+
+        assert!(holder.join().unwrap(), "stores or bounded updates waited in line");
+    });
+}
+
+// Counts how often it is cloned from scratch and how often an existing copy is overwritten.
+struct Tracked {
+    items: Vec<u64>,
+    clones: Arc<AtomicUsize>,
+    overwrites: Arc<AtomicUsize>,
+}
+
+impl Clone for Tracked {
+    fn clone(&self) -> Self {
+        self.clones.fetch_add(1, Ordering::Relaxed);
+        Self {
+            items: self.items.clone(),
+            clones: self.clones.clone(),
+            overwrites: self.overwrites.clone(),
+        }
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.overwrites.fetch_add(1, Ordering::Relaxed);
+        self.items.clone_from(&source.items);
+    }
+}
+
+#[test]
+fn rcu_reuses_its_copy() {
+    let clones = Arc::new(AtomicUsize::new(0));
+    let overwrites = Arc::new(AtomicUsize::new(0));
+    let cell = AtomicCell::new(Tracked {
+        items: vec![1],
+        clones: clones.clone(),
+        overwrites: overwrites.clone(),
+    });
+
+    let len = cell.rcu(|t| {
+        t.items.push(2);
+        t.items.len()
+    })
+    .unwrap();
+    assert_eq!(len, 2);
+    assert_eq!(cell.load().items, [1, 2]);
+    assert_eq!((clones.load(Ordering::Relaxed), overwrites.load(Ordering::Relaxed)), (1, 0));
+
+    // Losing the cas twice: one clone for the first attempt, then the copy is overwritten for each retry.
+    let mut calls = 0;
+    cell.rcu(|t| {
+        calls += 1;
+        if calls < 3 {
+            cell.store_arc(Arc::new(Tracked {
+                items: vec![10 * calls],
+                clones: clones.clone(),
+                overwrites: overwrites.clone(),
+            }));
+        }
+        t.items.push(3);
+    })
+    .unwrap();
+    assert_eq!(calls, 3);
+    assert_eq!(cell.load().items, [20, 3]);
+    assert_eq!((clones.load(Ordering::Relaxed), overwrites.load(Ordering::Relaxed)), (2, 2));
+}
+
+#[test]
+fn rcu_summing() {
+    let fancy_cell = AtomicCell::new(Vec::<u64>::new());
+
+    thread::scope(|s| {
+        for i in 0..8 {
+            let fancy_cell = &fancy_cell;
+            s.spawn(move || {
+                for j in 0..250 {
+                    fancy_cell.rcu(|v| v.push(i * 1000 + j)).unwrap();
+                }
+            });
+        }
+    });
+
+    let mut all = (*fancy_cell.load()).clone();
+    all.sort_unstable();
+    let expected: Vec<u64> = (0..8).flat_map(|i| (0..250).map(move |j| i * 1000 + j)).collect();
+    assert_eq!(all, expected);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn panic_policies() {
+    let nope = |_: Arc<u64>| -> (Arc<u64>, ()) { panic!("nope") };
+
+    // The default hands the panic back.
+    let cell = AtomicCell::new(1u64);
+    assert!(matches!(cell.fetch_update(nope), Err(UpdateError::Panicked(_))));
+    assert!(!cell.is_poisoned());
+    assert_eq!(cell.fetch_update(|x| (Arc::new(*x + 1), *x)).unwrap(), 1);
+
+    let cell = AtomicCell::new(1u64).panic_policy(PanicPolicy::Propagate);
+    let unwound = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| cell.fetch_update(nope)));
+    assert!(unwound.is_err());
+    assert_eq!(cell.fetch_update(|x| (Arc::new(*x + 1), *x)).unwrap(), 1);
+
+    let cell = AtomicCell::new(1u64).panic_policy(PanicPolicy::Poison);
+    assert!(matches!(cell.try_fetch_update(3, Backoff::Spin, nope), Err(UpdateError::Panicked(_))));
+    assert!(cell.is_poisoned());
+
+    // Refused without calling the closure.
+    let mut calls = 0;
+    let refused = cell.fetch_update(|x| {
+        calls += 1;
+        (x, ())
+    });
+    assert!(matches!(refused, Err(UpdateError::Poisoned)));
+    assert_eq!(calls, 0);
+    assert!(matches!(cell.try_fetch_update(0, Backoff::Spin, |x| (x, ())), Err(UpdateError::Poisoned)));
+    assert!(matches!(cell.fetch_update_until(Instant::now(), Backoff::Spin, |x| (x, ())), Err(UpdateError::Poisoned)));
+
+    cell.clear_poison();
+    assert_eq!(cell.fetch_update(|x| (Arc::new(*x + 1), *x)).unwrap(), 1);
+    assert_eq!(*cell.load(), 2);
+}
+
+#[test]
+fn every_update_poisons_and_refuses() {
+    // Nothing catches a panic in 'try_update', the cell is poisoned on the way out. Same without std.
+    let cell = AtomicCell::new(1u64).panic_policy(PanicPolicy::Poison);
+    let unwound = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| cell.try_update(|_| -> Option<u64> { panic!("nope") })));
+    assert!(unwound.is_err());
+    assert!(cell.is_poisoned());
+
+    assert!(matches!(cell.try_update(|x| Some(x + 1)), Err(UpdateError::Poisoned)));
+    assert!(matches!(cell.update_and_fetch(|x| Some(x + 1)), Err(UpdateError::Poisoned)));
+    assert!(matches!(cell.rcu(|x| *x += 1), Err(UpdateError::Poisoned)));
+    assert!(matches!(cell.fetch_add(1), Err(UpdateError::Poisoned)));
+    assert!(matches!(cell.fetch_xor(1), Err(UpdateError::Poisoned)));
+    assert!(matches!(cell.cas_by_eq(&1, 2), Err(UpdateError::Poisoned)));
+    assert!(matches!(cell.fetch_update(|x| (x, ())), Err(UpdateError::Poisoned)));
+    assert_eq!(*cell.load(), 1);
+
+    // Plain stores don't run a closure, they still work.
+    cell.store(5);
+    cell.clear_poison();
+    assert_eq!(*cell.fetch_add(1).unwrap(), 5);
+    assert!(cell.cas_by_eq(&6, 7).unwrap().is_ok());
+
+    // Only 'Poison' poisons.
+    let cell = AtomicCell::new(1u64);
+    let unwound = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| cell.rcu(|_| panic!("nope"))));
+    assert!(unwound.is_err());
+    assert!(!cell.is_poisoned());
+    assert_eq!(*cell.fetch_add(1).unwrap(), 1);
+}